@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use hashbrown::HashMap;
+use rand::{Rng, SeedableRng};
+use rstar::{RTree, RTreeObject, AABB};
+
+use atlas_packer::{
+    pack::AtlasPacker,
+    texture::{DownsampleFactor, PolygonMappedTexture},
+};
+
+// Resident memory of the process in kB (Linux only)
+fn memory_kb(field: &str) -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with(field))?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+// Peak memory used by `f` above the memory in use when it starts, in MB
+fn measure<T>(f: impl FnOnce() -> T) -> (T, std::time::Duration, Option<u64>) {
+    // Writing 5 resets the peak resident memory to the current one (Linux only)
+    let reset = std::fs::write("/proc/self/clear_refs", "5").is_ok();
+    let before = memory_kb("VmRSS:");
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let peak = match (reset, before, memory_kb("VmHWM:")) {
+        (true, Some(before), Some(peak)) => Some(peak.saturating_sub(before) / 1024),
+        _ => None,
+    };
+    (result, elapsed, peak)
+}
+
+struct Rectangle {
+    index: usize,
+    envelope: AABB<[f32; 2]>,
+}
+
+impl RTreeObject for Rectangle {
+    type Envelope = AABB<[f32; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
+}
+
+// Union-find with a recursive root and no balancing, as used before the partitioned clustering
+struct RecursiveDisjointSet {
+    parent: Vec<usize>,
+}
+
+impl RecursiveDisjointSet {
+    fn root(&self, x: usize) -> usize {
+        if self.parent[x] == x {
+            x
+        } else {
+            self.root(self.parent[x])
+        }
+    }
+
+    fn unite(&mut self, x: usize, y: usize) {
+        let root_x = self.root(x);
+        let root_y = self.root(y);
+        self.parent[root_x] = root_y;
+    }
+}
+
+// The clustering before it was partitioned: one R-tree for all polygons, filled one insert at a
+// time, and every hit filtered by source image. Unlike `AtlasPacker::clusters`, it only groups
+// the polygon IDs and does not build the cluster textures.
+fn old_clusters(textures: &[(String, PolygonMappedTexture)]) -> Vec<Vec<String>> {
+    let envelope = |texture: &PolygonMappedTexture| {
+        let (min_x, min_y, max_x, max_y) = texture.bbox();
+        AABB::from_corners([min_x as f32, min_y as f32], [max_x as f32, max_y as f32])
+    };
+    let mut rtree = RTree::new();
+    for (index, (_, texture)) in textures.iter().enumerate() {
+        rtree.insert(Rectangle {
+            index,
+            envelope: envelope(texture),
+        });
+    }
+
+    let mut disjoint_set = RecursiveDisjointSet {
+        parent: (0..textures.len()).collect(),
+    };
+    for (i, (_, texture)) in textures.iter().enumerate() {
+        let hits = rtree
+            .locate_in_envelope_intersecting(&envelope(texture))
+            .filter(|target| textures[target.index].1.image_path == texture.image_path);
+        for target in hits {
+            if i < target.index {
+                disjoint_set.unite(i, target.index);
+            }
+        }
+    }
+
+    let mut clusters: HashMap<usize, Vec<String>> = HashMap::new();
+    for (i, (polygon_id, _)) in textures.iter().enumerate() {
+        clusters
+            .entry(disjoint_set.root(i))
+            .or_default()
+            .push(polygon_id.clone());
+    }
+    clusters.into_values().collect()
+}
+
+fn main() {
+    // Usage: cargo run --release --example bench_clustering [polygon count]
+    // Only the clustering is timed; nothing is placed or exported, so no images are read
+    let polygon_count = std::env::args()
+        .nth(1)
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(1_000_000usize);
+    let polygons_per_image = 1_000;
+
+    // Small facade-like triangles on 4096x4096 source images; neighbours often overlap
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let textures = (0..polygon_count)
+        .map(|i| {
+            let image_path = PathBuf::from(format!("image_{}.jpg", i / polygons_per_image));
+            let (u, v) = (rng.gen_range(0.0..0.99), rng.gen_range(0.01..1.0));
+            let (width, height) = (rng.gen_range(0.001..0.01), rng.gen_range(0.001..0.01));
+            let texture = PolygonMappedTexture::new(
+                &image_path,
+                (4096, 4096),
+                &[(u, v), (u + width, v), (u + width, v - height)],
+                DownsampleFactor::new(&1.0),
+            );
+            (format!("polygon_{}", i), texture)
+        })
+        .collect::<Vec<_>>();
+
+    let (old, old_time, old_memory) = measure(|| old_clusters(&textures));
+    println!(
+        "Before: {} clusters in {:?} (one R-tree, recursive union-find; IDs only)",
+        old.len(),
+        old_time
+    );
+    drop(old);
+
+    let mut packer = AtlasPacker::default();
+    for (polygon_id, texture) in textures {
+        packer.add_texture(polygon_id, texture);
+    }
+    let (new, new_time, new_memory) = measure(|| packer.clusters());
+    println!(
+        "After: {} clusters in {:?} (R-tree per source image; with the cluster textures)",
+        new.len(),
+        new_time
+    );
+
+    match (old_memory, new_memory) {
+        (Some(old), Some(new)) => {
+            println!(
+                "Peak memory while clustering: {} MB before, {} MB after",
+                old, new
+            )
+        }
+        _ => println!("Peak memory is only measured on Linux"),
+    }
+}
@@ -7,7 +7,7 @@ use atlas_packer::texture::PolygonMappedTexture;
 use rayon::prelude::*;
 
 use atlas_packer::{
-    pack::AtlasPacker,
+    pack::{AtlasPacker, ClusterSortStrategy, SortOrder},
     place::{GuillotineTexturePlacer, TexturePlacerConfig},
     texture::{
         cache::{TextureCache, TextureSizeCache},
@@ -54,13 +54,12 @@ fn main() {
     }
 
     // initialize texture packer
-    let config = TexturePlacerConfig {
-        width: 4096,
-        height: 4096,
-        padding: 0,
-    };
+    let config = TexturePlacerConfig::new(4096, 4096, 0);
 
-    let packer = Mutex::new(AtlasPacker::default());
+    let packer = Mutex::new(
+        AtlasPacker::default()
+            .with_sort_strategy(ClusterSortStrategy::Height(SortOrder::Descending)),
+    );
 
     let packing_start = Instant::now();
 
@@ -96,13 +95,7 @@ fn main() {
     // Caches the original textures for exporting to an atlas.
     let texture_cache = TextureCache::new(100_000_000);
     let output_dir = Path::new("./examples/output/");
-    packed.export(
-        WebpAtlasExporter::default(),
-        output_dir,
-        &texture_cache,
-        config.width(),
-        config.height(),
-    );
+    packed.export(WebpAtlasExporter::default(), output_dir, &texture_cache);
     let duration = start.elapsed();
     println!("all atlas export process {:?}", duration);
 
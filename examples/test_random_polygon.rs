@@ -91,11 +91,7 @@ fn main() {
     }
 
     // initialize texture packer
-    let config = TexturePlacerConfig {
-        width: 4096,
-        height: 4096,
-        padding: 0,
-    };
+    let config = TexturePlacerConfig::new(4096, 4096, 0);
 
     let packer = Mutex::new(AtlasPacker::default());
 
@@ -124,13 +120,7 @@ fn main() {
     let texture_cache = TextureCache::new(100_000_000);
     let output_dir = Path::new("./examples/output/");
 
-    packed.export(
-        JpegAtlasExporter::default(),
-        output_dir,
-        &texture_cache,
-        config.width(),
-        config.height(),
-    );
+    packed.export(JpegAtlasExporter::default(), output_dir, &texture_cache);
     let mut count = 0;
     let count_limit = 20;
     polygons.iter().for_each(|polygon| {
@@ -139,8 +129,8 @@ fn main() {
         }
         count += 1;
         if let Some(info) = packed.get_texture_info(&polygon.id) {
-            let pixel_coords =
-                uv_to_pixel_coords(&info.placed_uv_coords, config.width, config.height);
+            let (width, height) = packed.get_page_size(info.atlas_id).unwrap();
+            let pixel_coords = uv_to_pixel_coords(&info.placed_uv_coords, width, height);
 
             let tex_bbox = calc_bbox(&pixel_coords);
 
@@ -149,6 +139,7 @@ fn main() {
             let mut image = image::open(atlas_uri).unwrap();
 
             // crop image
+            #[allow(clippy::unnecessary_cast)]
             let cropped_image = image.crop(
                 tex_bbox.0 as u32,
                 tex_bbox.1 as u32,
@@ -1,42 +1,57 @@
-/// Very simple disjoint set implementation for clustering cropped textures
+/// Disjoint set (union-find) for clustering cropped textures
 /// - fixed size
 /// - cannot divide the union
+/// - union by rank and iterative path compression, so long chains cannot overflow the stack
 pub struct DisjointSet {
     parent: Vec<usize>,
+    rank: Vec<u8>,
 }
 
 impl DisjointSet {
     pub fn new(num_elements: usize) -> Self {
         DisjointSet {
             parent: (0..num_elements).collect(),
+            rank: vec![0; num_elements],
         }
     }
 
     #[inline]
-    pub fn root(&self, x: usize) -> usize {
-        if self.parent[x] == x {
-            x
-        } else {
-            self.root(self.parent[x])
+    pub fn root(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
         }
+        // Point every element on the path directly to the root
+        let mut current = x;
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+        root
     }
 
     #[inline]
     pub fn unite(&mut self, x: usize, y: usize) {
         let root_x = self.root(x);
         let root_y = self.root(y);
-        self.parent[root_x] = root_y;
+        if root_x == root_y {
+            return;
+        }
+        match self.rank[root_x].cmp(&self.rank[root_y]) {
+            std::cmp::Ordering::Less => self.parent[root_x] = root_y,
+            std::cmp::Ordering::Greater => self.parent[root_y] = root_x,
+            std::cmp::Ordering::Equal => {
+                self.parent[root_x] = root_y;
+                self.rank[root_y] += 1;
+            }
+        }
     }
 
     #[allow(dead_code)]
-    fn is_same(&self, x: usize, y: usize) -> bool {
+    fn is_same(&mut self, x: usize, y: usize) -> bool {
         self.root(x) == self.root(y)
     }
-
-    #[inline]
-    pub fn compress(&mut self) {
-        self.parent = self.parent.iter().map(|&x| self.root(x)).collect();
-    }
 }
 
 #[cfg(test)]
@@ -54,4 +69,18 @@ mod tests {
         ds.unite(0, 3);
         assert!(ds.is_same(0, 4));
     }
+
+    #[test]
+    fn test_disjoint_set_long_chain() {
+        // Union by rank never builds a deep tree, so the chain is built directly; a recursive
+        // root() would overflow the stack on it
+        let count = 1_000_000;
+        let mut ds = DisjointSet::new(count);
+        for i in 0..count - 1 {
+            ds.parent[i] = i + 1;
+        }
+        assert_eq!(ds.root(0), count - 1);
+        // The path is compressed
+        assert!((0..count).all(|i| ds.parent[i] == count - 1));
+    }
 }
@@ -158,10 +158,19 @@ fn create_atlas_rgba(
     atlas_data.par_iter().for_each(|info| {
         let texture = textures.get(&info.cluster_id).unwrap();
         let cropped = texture.crop(&texture_cache.get_image(&texture.image_path));
-        let image = cropped.as_rgba8().unwrap();
+        let rotated;
+        let image = if info.rotated {
+            rotated = image::imageops::rotate90(cropped.as_rgba8().unwrap());
+            &rotated
+        } else {
+            cropped.as_rgba8().unwrap()
+        };
 
         let mut atlas_image = atlas_image.lock().unwrap();
         for (x, y, pixel) in image.enumerate_pixels() {
+            if info.mask.as_ref().is_some_and(|mask| !mask.contains(x, y)) {
+                continue;
+            }
             let atlas_x = info.origin.0 + x;
             let atlas_y = info.origin.1 + y;
             atlas_image.put_pixel(atlas_x, atlas_y, *pixel);
@@ -183,10 +192,17 @@ fn create_atlas_image_rgb(
     atlas_data.par_iter().for_each(|info| {
         let texture = textures.get(&info.cluster_id).unwrap();
         let cropped = texture.crop(&texture_cache.get_image(&texture.image_path));
-        let image = cropped.to_rgb8();
+        let image = if info.rotated {
+            image::imageops::rotate90(&cropped.to_rgb8())
+        } else {
+            cropped.to_rgb8()
+        };
 
         let mut atlas_image = atlas_image.lock().unwrap();
         for (x, y, pixel) in image.enumerate_pixels() {
+            if info.mask.as_ref().is_some_and(|mask| !mask.contains(x, y)) {
+                continue;
+            }
             let atlas_x = info.origin.0 + x;
             let atlas_y = info.origin.1 + y;
             atlas_image.put_pixel(atlas_x, atlas_y, *pixel);
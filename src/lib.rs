@@ -1,5 +1,7 @@
 mod disjoint_set;
 pub mod export;
+#[cfg(feature = "serde")]
+pub mod manifest;
 pub mod pack;
 pub mod place;
 pub mod texture;
@@ -7,3 +9,4 @@ pub mod texture;
 pub type ClusterID = String;
 pub type AtlasID = usize;
 pub type PolygonID = String;
+pub type GroupKey = String;
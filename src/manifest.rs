@@ -0,0 +1,542 @@
+use std::path::PathBuf;
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::pack::{Cluster, OversizeOutcome, PackedAtlasProvider};
+use crate::place::{PlacedTextureGeometry, PlacedUVPolygon};
+use crate::texture::{ChildUVPolygon, ClusterBoundingTexture, DownsampleFactor, LocationHint};
+use crate::{AtlasID, ClusterID, GroupKey, PolygonID};
+
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Serializable description of a pack result, sorted by ID so that the output is stable
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub version: u32,
+    pub pages: Vec<PageManifest>,
+    pub clusters: Vec<ClusterManifest>,
+    pub polygons: Vec<PolygonManifest>,
+    // The pages were shrunk to their used area when packed
+    #[serde(default)]
+    pub pages_shrunk: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageManifest {
+    pub atlas_id: AtlasID,
+    pub width: u32,
+    pub height: u32,
+    pub placements: Vec<PlacedTextureGeometry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterManifest {
+    pub cluster_id: ClusterID,
+    pub image_path: PathBuf,
+    // (x, y, width, height) of the crop in the source image, without the buffer
+    pub crop_rect: (u32, u32, u32, u32),
+    pub buffer: u32,
+    pub downsample_factor: f32,
+    // UV coordinates of the polygons on the cropped texture
+    pub children: Vec<(PolygonID, ChildUVPolygon)>,
+    #[serde(default)]
+    pub group: Option<GroupKey>,
+    #[serde(default)]
+    pub location: Option<LocationHint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolygonManifest {
+    pub polygon_id: PolygonID,
+    // None for skipped polygons
+    pub placement: Option<PolygonPlacement>,
+    pub oversize: Option<OversizeOutcome>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolygonPlacement {
+    pub cluster_id: ClusterID,
+    pub atlas_id: AtlasID,
+    // UV coordinates on atlas
+    pub uv_coords: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ManifestError {
+    #[error("unsupported manifest version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("downsample factor of cluster {0} is not between 0 and 1")]
+    InvalidDownsampleFactor(ClusterID),
+    #[error("unknown cluster: {0}")]
+    UnknownCluster(ClusterID),
+    #[error("unknown page: {0}")]
+    UnknownPage(AtlasID),
+    #[error("cluster {0} is not placed on page {1}")]
+    AtlasMismatch(ClusterID, AtlasID),
+    #[error("placement of cluster {0} does not fit its page")]
+    PlacementOutOfBounds(ClusterID),
+    #[error("placement of cluster {0} does not match the size of its texture")]
+    PlacementSizeMismatch(ClusterID),
+    #[error("placement mask of cluster {0} does not match its grid")]
+    InvalidMask(ClusterID),
+    #[error("cluster {0} is placed more than once")]
+    DuplicatePlacement(ClusterID),
+}
+
+impl PackManifest {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    #[cfg(feature = "bincode")]
+    pub fn to_bincode(&self) -> bincode::Result<Vec<u8>> {
+        bincode::serialize(self)
+    }
+
+    #[cfg(feature = "bincode")]
+    pub fn from_bincode(bytes: &[u8]) -> bincode::Result<Self> {
+        bincode::deserialize(bytes)
+    }
+}
+
+impl PackedAtlasProvider {
+    /// Rebuild a pack result from a manifest, e.g. to export the atlases again in another format
+    pub fn from_manifest(manifest: &PackManifest) -> Result<Self, ManifestError> {
+        if manifest.version != MANIFEST_VERSION {
+            return Err(ManifestError::UnsupportedVersion(manifest.version));
+        }
+
+        let mut clusters = HashMap::new();
+        for cluster in &manifest.clusters {
+            if !(0.0..=1.0).contains(&cluster.downsample_factor) {
+                return Err(ManifestError::InvalidDownsampleFactor(
+                    cluster.cluster_id.clone(),
+                ));
+            }
+            let bounding_texture = ClusterBoundingTexture::from_crop_rect(
+                &cluster.image_path,
+                cluster.crop_rect,
+                cluster.buffer,
+                DownsampleFactor::new(&cluster.downsample_factor),
+            );
+            clusters.insert(
+                cluster.cluster_id.clone(),
+                Cluster {
+                    bounding_texture,
+                    uv_polygons: cluster.children.clone(),
+                    group: cluster.group.clone(),
+                    location: cluster.location,
+                },
+            );
+        }
+
+        let mut atlases = HashMap::new();
+        let mut page_sizes = HashMap::new();
+        // Page of each placed cluster
+        let mut cluster_pages = HashMap::new();
+        for page in &manifest.pages {
+            for placed in &page.placements {
+                let Some(cluster) = clusters.get(&placed.cluster_id) else {
+                    return Err(ManifestError::UnknownCluster(placed.cluster_id.clone()));
+                };
+                if placed.atlas_id != page.atlas_id {
+                    return Err(ManifestError::AtlasMismatch(
+                        placed.cluster_id.clone(),
+                        placed.atlas_id,
+                    ));
+                }
+                // The exporters write the whole downsampled crop, so the placement must have
+                // its size
+                let (width, height) = cluster.bounding_texture.get_scaled_size();
+                let size = if placed.rotated {
+                    (height, width)
+                } else {
+                    (width, height)
+                };
+                if (placed.width, placed.height) != size {
+                    return Err(ManifestError::PlacementSizeMismatch(
+                        placed.cluster_id.clone(),
+                    ));
+                }
+                if placed.mask.as_ref().is_some_and(|mask| {
+                    mask.cell_size == 0
+                        || mask.cells.len() as u64 != mask.columns as u64 * mask.rows as u64
+                }) {
+                    return Err(ManifestError::InvalidMask(placed.cluster_id.clone()));
+                }
+                // The exporters write every texel of the placement onto the page
+                let fits = |origin: u32, size: u32, page_size: u32| {
+                    origin.checked_add(size).is_some_and(|end| end <= page_size)
+                };
+                if !fits(placed.origin.0, placed.width, page.width)
+                    || !fits(placed.origin.1, placed.height, page.height)
+                {
+                    return Err(ManifestError::PlacementOutOfBounds(
+                        placed.cluster_id.clone(),
+                    ));
+                }
+                if cluster_pages
+                    .insert(&placed.cluster_id, page.atlas_id)
+                    .is_some()
+                {
+                    return Err(ManifestError::DuplicatePlacement(placed.cluster_id.clone()));
+                }
+            }
+            atlases.insert(page.atlas_id, page.placements.clone());
+            page_sizes.insert(page.atlas_id, (page.width, page.height));
+        }
+
+        let mut placed_uv_polygon_map = HashMap::new();
+        let mut oversized = HashMap::new();
+        for polygon in &manifest.polygons {
+            if let Some(placement) = &polygon.placement {
+                if !clusters.contains_key(&placement.cluster_id) {
+                    return Err(ManifestError::UnknownCluster(placement.cluster_id.clone()));
+                }
+                if !atlases.contains_key(&placement.atlas_id) {
+                    return Err(ManifestError::UnknownPage(placement.atlas_id));
+                }
+                if cluster_pages.get(&placement.cluster_id) != Some(&placement.atlas_id) {
+                    return Err(ManifestError::AtlasMismatch(
+                        placement.cluster_id.clone(),
+                        placement.atlas_id,
+                    ));
+                }
+                placed_uv_polygon_map.insert(
+                    polygon.polygon_id.clone(),
+                    PlacedUVPolygon {
+                        polygon_id: polygon.polygon_id.clone(),
+                        cluster_id: placement.cluster_id.clone(),
+                        atlas_id: placement.atlas_id,
+                        placed_uv_coords: placement.uv_coords.clone(),
+                    },
+                );
+            }
+            if let Some(outcome) = &polygon.oversize {
+                oversized.insert(polygon.polygon_id.clone(), outcome.clone());
+            }
+        }
+
+        Ok(PackedAtlasProvider {
+            atlases,
+            clusters,
+            page_sizes,
+            placed_uv_polygon_map,
+            oversized,
+            pages_shrunk: manifest.pages_shrunk,
+        })
+    }
+
+    pub fn to_manifest(&self) -> PackManifest {
+        let mut pages = self
+            .atlases
+            .iter()
+            .map(|(atlas_id, atlas)| {
+                let (width, height) = self.page_sizes[atlas_id];
+                let mut placements = atlas.clone();
+                placements.sort_by(|a, b| a.cluster_id.cmp(&b.cluster_id));
+                PageManifest {
+                    atlas_id: *atlas_id,
+                    width,
+                    height,
+                    placements,
+                }
+            })
+            .collect::<Vec<_>>();
+        pages.sort_by_key(|page| page.atlas_id);
+
+        let mut clusters = self
+            .clusters
+            .iter()
+            .map(|(cluster_id, cluster)| {
+                let texture = &cluster.bounding_texture;
+                ClusterManifest {
+                    cluster_id: cluster_id.clone(),
+                    image_path: texture.image_path.clone(),
+                    crop_rect: texture.get_crop_rect(),
+                    buffer: texture.buffer,
+                    downsample_factor: texture.downsample_factor.value(),
+                    children: cluster.uv_polygons.clone(),
+                    group: cluster.group.clone(),
+                    location: cluster.location,
+                }
+            })
+            .collect::<Vec<_>>();
+        clusters.sort_by(|a, b| a.cluster_id.cmp(&b.cluster_id));
+
+        let mut polygon_ids = self
+            .placed_uv_polygon_map
+            .keys()
+            .chain(self.oversized.keys())
+            .collect::<Vec<_>>();
+        polygon_ids.sort();
+        polygon_ids.dedup();
+        let polygons = polygon_ids
+            .into_iter()
+            .map(|polygon_id| PolygonManifest {
+                polygon_id: polygon_id.clone(),
+                placement: self.placed_uv_polygon_map.get(polygon_id).map(|placed| {
+                    PolygonPlacement {
+                        cluster_id: placed.cluster_id.clone(),
+                        atlas_id: placed.atlas_id,
+                        uv_coords: placed.placed_uv_coords.clone(),
+                    }
+                }),
+                oversize: self.oversized.get(polygon_id).cloned(),
+            })
+            .collect();
+
+        PackManifest {
+            version: MANIFEST_VERSION,
+            pages,
+            clusters,
+            polygons,
+            pages_shrunk: self.pages_shrunk,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::pack::{AtlasPacker, OversizePolicy};
+    use crate::place::{GuillotineTexturePlacer, PlacementMask, TexturePlacerConfig};
+    use crate::texture::{DownsampleFactor, PolygonMappedTexture};
+
+    fn packed() -> PackedAtlasProvider {
+        let mut packer = AtlasPacker::default().with_oversize_policy(OversizePolicy::Skip);
+        for (i, (offset, size)) in [(0, 20), (10, 20), (100, 30), (300, 100)]
+            .into_iter()
+            .enumerate()
+        {
+            let (min, max) = (offset as f64 / 1024.0, (offset + size) as f64 / 1024.0);
+            packer.add_texture(
+                format!("polygon_{}", i),
+                PolygonMappedTexture::new(
+                    &PathBuf::from("a.png"),
+                    (1024, 1024),
+                    &[(min, 1.0 - min), (max, 1.0 - min), (max, 1.0 - max)],
+                    DownsampleFactor::new(&0.5),
+                )
+                .with_location(LocationHint::TileKey(i as u64)),
+            );
+        }
+        packer.pack(GuillotineTexturePlacer::new(TexturePlacerConfig::new(
+            32, 32, 1,
+        )))
+    }
+
+    #[test]
+    fn test_manifest() {
+        let packed = packed();
+        let manifest = packed.to_manifest();
+        assert_eq!(manifest.version, MANIFEST_VERSION);
+        assert_eq!(manifest.clusters.len(), 2);
+        assert_eq!(manifest.polygons.len(), 4);
+        for page in &manifest.pages {
+            assert_eq!((page.width, page.height), (32, 32));
+        }
+
+        let merged = manifest
+            .clusters
+            .iter()
+            .find(|cluster| cluster.children.len() == 2)
+            .unwrap();
+        assert_eq!(merged.image_path, PathBuf::from("a.png"));
+        assert_eq!(merged.crop_rect, (0, 0, 30, 30));
+        assert_eq!(merged.buffer, 2);
+        assert_eq!(merged.downsample_factor, 0.5);
+
+        // The 100px polygon does not fit the page and is skipped
+        let skipped = &manifest.polygons[3];
+        assert_eq!(skipped.polygon_id, "polygon_3");
+        assert!(skipped.placement.is_none());
+        assert_eq!(skipped.oversize, Some(OversizeOutcome::Skipped));
+
+        for polygon in &manifest.polygons[..3] {
+            let placement = polygon.placement.as_ref().unwrap();
+            let info = packed.get_texture_info(&polygon.polygon_id).unwrap();
+            assert_eq!(placement.uv_coords, info.placed_uv_coords);
+            assert_eq!(placement.atlas_id, info.atlas_id);
+        }
+
+        // The output does not depend on the hash map order
+        assert_eq!(packed.to_manifest(), manifest);
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let manifest = packed().to_manifest();
+        let json = manifest.to_json().unwrap();
+        assert_eq!(PackManifest::from_json(&json).unwrap(), manifest);
+
+        // The rebuilt pack result keeps everything, including the location hints
+        assert!(manifest
+            .clusters
+            .iter()
+            .all(|cluster| cluster.location.is_some()));
+        let restored = PackedAtlasProvider::from_manifest(&manifest).unwrap();
+        assert_eq!(restored.to_manifest(), manifest);
+
+        #[cfg(feature = "bincode")]
+        {
+            let bytes = manifest.to_bincode().unwrap();
+            assert!(bytes.len() < json.len());
+            assert_eq!(PackManifest::from_bincode(&bytes).unwrap(), manifest);
+        }
+    }
+
+    #[test]
+    fn test_export_from_manifest() {
+        use image::{ImageBuffer, Rgba};
+
+        use crate::export::PngAtlasExporter;
+        use crate::texture::cache::TextureCache;
+
+        let dir = tempfile::tempdir().unwrap();
+        let image_path = dir.path().join("source.png");
+        ImageBuffer::from_fn(64, 64, |x, y| {
+            Rgba([x as u8 * 4, y as u8 * 4, (x ^ y) as u8, 255])
+        })
+        .save(&image_path)
+        .unwrap();
+
+        let mut packer = AtlasPacker::default();
+        for (i, (x, y, width, height)) in [(0, 0, 10, 30), (20, 5, 30, 12), (40, 40, 20, 20)]
+            .into_iter()
+            .enumerate()
+        {
+            let (min_u, max_u) = (x as f64 / 64.0, (x + width) as f64 / 64.0);
+            let (min_v, max_v) = (1.0 - (y + height) as f64 / 64.0, 1.0 - y as f64 / 64.0);
+            packer.add_texture(
+                format!("polygon_{}", i),
+                PolygonMappedTexture::new(
+                    &image_path,
+                    (64, 64),
+                    &[
+                        (min_u, min_v),
+                        (max_u, min_v),
+                        (max_u, max_v),
+                        (min_u, max_v),
+                    ],
+                    DownsampleFactor::new(&1.0),
+                ),
+            );
+        }
+        let config = TexturePlacerConfig::new(64, 64, 1).with_rotation(true);
+        let packed = packer.pack(GuillotineTexturePlacer::new(config));
+
+        let packed_dir = dir.path().join("packed");
+        let restored_dir = dir.path().join("restored");
+        std::fs::create_dir_all(&packed_dir).unwrap();
+        std::fs::create_dir_all(&restored_dir).unwrap();
+        let texture_cache = TextureCache::new(100_000_000);
+        packed.export(PngAtlasExporter::default(), &packed_dir, &texture_cache);
+
+        let json = packed.to_manifest().to_json().unwrap();
+        let restored =
+            PackedAtlasProvider::from_manifest(&PackManifest::from_json(&json).unwrap()).unwrap();
+        restored.export(PngAtlasExporter::default(), &restored_dir, &texture_cache);
+
+        assert!(!packed.atlases.is_empty());
+        for atlas_id in packed.atlases.keys() {
+            let file_name = format!("{}.png", atlas_id);
+            let original = image::open(packed_dir.join(&file_name)).unwrap();
+            let restored = image::open(restored_dir.join(&file_name)).unwrap();
+            assert_eq!(original.to_rgba8(), restored.to_rgba8());
+        }
+    }
+
+    #[test]
+    fn test_invalid_manifest() {
+        let manifest = packed().to_manifest();
+
+        let mut unsupported = manifest.clone();
+        unsupported.version = MANIFEST_VERSION + 1;
+        assert_eq!(
+            PackedAtlasProvider::from_manifest(&unsupported).err(),
+            Some(ManifestError::UnsupportedVersion(MANIFEST_VERSION + 1))
+        );
+
+        let mut invalid_factor = manifest.clone();
+        invalid_factor.clusters[0].downsample_factor = 2.0;
+        assert!(matches!(
+            PackedAtlasProvider::from_manifest(&invalid_factor),
+            Err(ManifestError::InvalidDownsampleFactor(_))
+        ));
+
+        let mut missing_cluster = manifest.clone();
+        missing_cluster.clusters.clear();
+        assert!(matches!(
+            PackedAtlasProvider::from_manifest(&missing_cluster),
+            Err(ManifestError::UnknownCluster(_))
+        ));
+
+        // A placement listed on another page than its own atlas ID
+        let mut wrong_page = manifest.clone();
+        wrong_page.pages[0].placements[0].atlas_id += 1;
+        assert!(matches!(
+            PackedAtlasProvider::from_manifest(&wrong_page),
+            Err(ManifestError::AtlasMismatch(_, _))
+        ));
+
+        // A polygon on another page than its cluster
+        let mut wrong_polygon_page = manifest.clone();
+        let placement = wrong_polygon_page.polygons[0].placement.as_mut().unwrap();
+        placement.atlas_id = manifest
+            .pages
+            .iter()
+            .map(|page| page.atlas_id)
+            .find(|atlas_id| *atlas_id != placement.atlas_id)
+            .unwrap();
+        assert!(matches!(
+            PackedAtlasProvider::from_manifest(&wrong_polygon_page),
+            Err(ManifestError::AtlasMismatch(_, _))
+        ));
+
+        let mut out_of_bounds = manifest.clone();
+        out_of_bounds.pages[0].placements[0].origin.0 = 30;
+        assert!(matches!(
+            PackedAtlasProvider::from_manifest(&out_of_bounds),
+            Err(ManifestError::PlacementOutOfBounds(_))
+        ));
+
+        // A placement smaller than the texture the exporters write would let it spill over
+        // the page
+        let mut shrunk = manifest.clone();
+        let placed = &mut shrunk.pages[0].placements[0];
+        (placed.width, placed.height) = (5, 5);
+        assert!(matches!(
+            PackedAtlasProvider::from_manifest(&shrunk),
+            Err(ManifestError::PlacementSizeMismatch(_))
+        ));
+
+        let mut invalid_mask = manifest.clone();
+        invalid_mask.pages[0].placements[0].mask = Some(PlacementMask {
+            cell_size: 4,
+            columns: 2,
+            rows: 2,
+            cells: vec![true; 3],
+        });
+        assert!(matches!(
+            PackedAtlasProvider::from_manifest(&invalid_mask),
+            Err(ManifestError::InvalidMask(_))
+        ));
+
+        let mut duplicate = manifest;
+        let placed = duplicate.pages[0].placements[0].clone();
+        duplicate.pages[0].placements.push(placed);
+        assert!(matches!(
+            PackedAtlasProvider::from_manifest(&duplicate),
+            Err(ManifestError::DuplicatePlacement(_))
+        ));
+    }
+}
@@ -1,27 +1,381 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
 use std::path::Path;
+use std::sync::Arc;
 
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use rayon::prelude::*;
 use rstar::{RTree, RTreeObject, AABB};
+use thiserror::Error;
 
 use crate::disjoint_set::DisjointSet;
 use crate::export::AtlasExporter;
-use crate::place::{PlacedTextureGeometry, PlacedUVPolygon, TexturePlacer};
+use crate::place::{
+    place_children, DynTexturePlacer, PlacedTextureGeometry, PlacedUVPolygon, TexturePlacer,
+    TexturePlacerConfig,
+};
 use crate::texture::cache::TextureCache;
-use crate::texture::{ChildUVPolygon, ClusterBoundingTexture, PolygonMappedTexture};
-use crate::{AtlasID, ClusterID, PolygonID};
+use crate::texture::utils::is_point_inside_polygon;
+use crate::texture::{
+    ChildUVPolygon, ClusterBoundingTexture, DownsampleFactor, LocationHint, PolygonMappedTexture,
+};
+use crate::{AtlasID, ClusterID, GroupKey, PolygonID};
 pub type Atlas = Vec<PlacedTextureGeometry>;
 
+pub type ClusterComparator =
+    Arc<dyn Fn(&ClusterBoundingTexture, &ClusterBoundingTexture) -> Ordering + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Descending,
+    Ascending,
+}
+
+/// Order in which clusters are fed to the placer
+#[derive(Clone, Default)]
+pub enum ClusterSortStrategy {
+    /// Hash map order (not deterministic)
+    #[default]
+    Unsorted,
+    Height(SortOrder),
+    Area(SortOrder),
+    MaxSide(SortOrder),
+    Perimeter(SortOrder),
+    /// Groups the clusters of each source image together, in path order
+    SourceImage(SortOrder),
+    Custom(ClusterComparator),
+    /// Keeps clusters that are close in the world next to each other, using the location hints
+    /// of the textures; clusters without a hint come last
+    Locality(SpaceFillingCurve),
+}
+
+/// Curve that maps the location hints to a one-dimensional order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpaceFillingCurve {
+    #[default]
+    Hilbert,
+    ZOrder,
+}
+
+// Grid resolution (bits per axis) of the world positions on the curve
+const CURVE_BITS: u32 = 16;
+
+impl SpaceFillingCurve {
+    fn index(&self, x: u32, y: u32) -> u64 {
+        match self {
+            SpaceFillingCurve::Hilbert => {
+                let n = 1u32 << CURVE_BITS;
+                let (mut x, mut y) = (x, y);
+                let mut index = 0;
+                let mut s = n / 2;
+                while s > 0 {
+                    let rx = (x & s > 0) as u32;
+                    let ry = (y & s > 0) as u32;
+                    index += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+                    // Rotate the quadrant so that the curve stays continuous
+                    if ry == 0 {
+                        if rx == 1 {
+                            x = n - 1 - x;
+                            y = n - 1 - y;
+                        }
+                        std::mem::swap(&mut x, &mut y);
+                    }
+                    s /= 2;
+                }
+                index
+            }
+            SpaceFillingCurve::ZOrder => (0..CURVE_BITS).fold(0, |index, bit| {
+                index
+                    | (((x >> bit) & 1) as u64) << (2 * bit)
+                    | (((y >> bit) & 1) as u64) << (2 * bit + 1)
+            }),
+        }
+    }
+
+    // (kind, index): positions first, then tile keys, then clusters without a hint
+    fn sort_keys(&self, clusters: &[(&ClusterID, &Cluster)]) -> Vec<(u8, u64)> {
+        let positions = clusters
+            .iter()
+            .filter_map(|(_, cluster)| match cluster.location {
+                Some(LocationHint::Position(x, y)) => Some((x, y)),
+                _ => None,
+            });
+        let (min_x, min_y, max_x, max_y) = positions.fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(min_x, min_y, max_x, max_y), (x, y)| {
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            },
+        );
+        let max_cell = ((1u32 << CURVE_BITS) - 1) as f64;
+        let to_cell = |value: f64, min: f64, max: f64| {
+            if max > min {
+                ((value - min) / (max - min) * max_cell).round() as u32
+            } else {
+                0
+            }
+        };
+
+        clusters
+            .iter()
+            .map(|(_, cluster)| match cluster.location {
+                Some(LocationHint::Position(x, y)) => (
+                    0,
+                    self.index(to_cell(x, min_x, max_x), to_cell(y, min_y, max_y)),
+                ),
+                Some(LocationHint::TileKey(key)) => (1, key),
+                None => (2, 0),
+            })
+            .collect()
+    }
+}
+
+impl ClusterSortStrategy {
+    fn compare(&self, a: &Cluster, b: &Cluster) -> Ordering {
+        let (a_texture, b_texture) = (&a.bounding_texture, &b.bounding_texture);
+        let (a_width, a_height) = a_texture.get_scaled_size();
+        let (b_width, b_height) = b_texture.get_scaled_size();
+        let (ordering, order) = match self {
+            ClusterSortStrategy::Unsorted => return Ordering::Equal,
+            ClusterSortStrategy::Height(order) => (a_height.cmp(&b_height), order),
+            ClusterSortStrategy::Area(order) => (
+                (a_width as u64 * a_height as u64).cmp(&(b_width as u64 * b_height as u64)),
+                order,
+            ),
+            ClusterSortStrategy::MaxSide(order) => {
+                (a_width.max(a_height).cmp(&b_width.max(b_height)), order)
+            }
+            ClusterSortStrategy::Perimeter(order) => {
+                ((a_width + a_height).cmp(&(b_width + b_height)), order)
+            }
+            ClusterSortStrategy::SourceImage(order) => {
+                (a_texture.image_path.cmp(&b_texture.image_path), order)
+            }
+            ClusterSortStrategy::Custom(comparator) => return comparator(a_texture, b_texture),
+            // Sorted by the keys of all clusters in `sort`
+            ClusterSortStrategy::Locality(_) => return Ordering::Equal,
+        };
+        match order {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+
+    fn sort<'a>(
+        &self,
+        clusters: impl IntoIterator<Item = (&'a ClusterID, &'a Cluster)>,
+    ) -> Vec<(&'a ClusterID, &'a Cluster)> {
+        let mut sorted = clusters.into_iter().collect::<Vec<_>>();
+        if let ClusterSortStrategy::Unsorted = self {
+            return sorted;
+        }
+        // Ties are broken by the smallest polygon ID so that the layout is reproducible
+        sorted.sort_by_cached_key(|(_, cluster)| cluster.min_polygon_id().cloned());
+        if let ClusterSortStrategy::Locality(curve) = self {
+            let keys = curve.sort_keys(&sorted);
+            let mut keyed = keys.into_iter().zip(sorted).collect::<Vec<_>>();
+            keyed.sort_by_key(|(key, _)| *key);
+            return keyed.into_iter().map(|(_, cluster)| cluster).collect();
+        }
+        sorted.sort_by(|(_, a), (_, b)| self.compare(a, b));
+        sorted
+    }
+}
+
+/// How the packer chooses the page for the next cluster
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageSelection {
+    /// Only the last page is kept open; it is closed as soon as a cluster does not fit
+    #[default]
+    Single,
+    /// All pages are kept open and the first one that can hold the cluster is used
+    FirstFit,
+    /// All pages are kept open and the fullest one that can hold the cluster is used
+    BestFit,
+}
+
+impl PageSelection {
+    fn select<P: TexturePlacer>(&self, pages: &[Page<P>], cluster: &Cluster) -> Option<AtlasID> {
+        match self {
+            PageSelection::Single => pages
+                .len()
+                .checked_sub(1)
+                .filter(|&index| pages[index].can_place(cluster)),
+            PageSelection::FirstFit => pages.iter().position(|page| page.can_place(cluster)),
+            PageSelection::BestFit => pages
+                .iter()
+                .enumerate()
+                .filter(|(_, page)| page.can_place(cluster))
+                .max_by_key(|(index, page)| (page.used_area, std::cmp::Reverse(*index)))
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+// Placer state of an atlas page
+struct Page<P> {
+    // None for the pages that cannot take more clusters (dedicated pages, or pages whose placer
+    // moved on to a new page)
+    placer: Option<P>,
+    atlas: Atlas,
+    used_area: u64,
+    size: (u32, u32),
+}
+
+impl<P: TexturePlacer> Page<P> {
+    fn new(placer: P) -> Self {
+        let size = (placer.config().width(), placer.config().height());
+        Page {
+            placer: Some(placer),
+            atlas: Vec::new(),
+            used_area: 0,
+            size,
+        }
+    }
+
+    fn can_place(&self, cluster: &Cluster) -> bool {
+        self.placer.as_ref().is_some_and(|placer| {
+            placer.can_place_cluster(&cluster.bounding_texture, &cluster.uv_polygons)
+        })
+    }
+}
+
+// Placer for a new page when there is no spare one: an empty copy of the last open placer, or that
+// placer itself, reset, for placers that fill one page at a time
+fn next_page_placer<P: TexturePlacer>(pages: &mut [Page<P>]) -> P {
+    let page = pages
+        .iter_mut()
+        .rev()
+        .find(|page| page.placer.is_some())
+        .expect("A placer is either spare or on an open page");
+    page.placer.as_ref().unwrap().new_page().unwrap_or_else(|| {
+        let mut placer = page.placer.take().unwrap();
+        placer.reset_param();
+        placer
+    })
+}
+
+/// Why `AtlasPacker::pack_into` refused a batch
+#[derive(Debug, Error, PartialEq)]
+pub enum PackIntoError {
+    #[error("polygons already packed: {0:?}")]
+    AlreadyPacked(Vec<PolygonID>),
+    #[error("the placer cannot reserve the existing placements")]
+    ReserveUnsupported,
+}
+
+/// What the packer does with a cluster that does not fit an empty page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OversizePolicy {
+    /// Downsample the cluster until it fits an empty page. A cluster that fits at no factor (for
+    /// example when the padding takes the whole page) is skipped.
+    #[default]
+    Downscale,
+    /// Put the cluster alone on a page large enough to hold it
+    DedicatedPage,
+    /// Split the polygons of the cluster into smaller clusters that fit an empty page,
+    /// keeping the full resolution. Single polygons that still do not fit are downscaled as with
+    /// `Downscale`.
+    Split,
+    /// Leave the cluster out of the atlas
+    Skip,
+}
+
+/// How an oversized cluster was handled, reported for each of its polygons
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OversizeOutcome {
+    /// Downsampled with the given factor
+    Downscaled(f32),
+    /// Placed alone on the given page
+    DedicatedPage(AtlasID),
+    /// Split into smaller clusters at full resolution
+    Split,
+    /// Not placed
+    Skipped,
+}
+
+// Number of halvings in the search of AtlasPacker::pack_to_page_count
+const SCALE_SEARCH_STEPS: usize = 12;
+
+// Number of halvings in the search of the texel budget scale
+const BUDGET_SEARCH_STEPS: usize = 24;
+
+/// How `AtlasPacker::with_texel_budget` reduces the clusters when the budget is exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BudgetReduction {
+    /// The same scale for every cluster
+    #[default]
+    Proportional,
+    /// The area kept by a cluster grows with the highest `PolygonMappedTexture::priority`
+    /// of its polygons
+    Priority,
+}
+
+// Largest downsample factor with which the texture fits an empty page, if any
+fn fit_downsample_factor<P: TexturePlacer>(
+    empty_placer: &P,
+    texture: &ClusterBoundingTexture,
+) -> Option<f32> {
+    let config = empty_placer.config();
+    let (_, _, buffered_width, buffered_height) = texture.get_buffered_geometry();
+    let max_width = config.width().saturating_sub(config.padding()) as f32;
+    let max_height = config.height().saturating_sub(config.padding()) as f32;
+
+    let mut factor = (max_width / buffered_width as f32).min(max_height / buffered_height as f32);
+    if config.allow_rotation() {
+        factor = factor
+            .max((max_width / buffered_height as f32).min(max_height / buffered_width as f32));
+    }
+    let mut factor = factor.min(texture.downsample_factor.value());
+
+    // Rounding in the placers may still reject the texture
+    let mut scaled = texture.clone();
+    while factor > f32::EPSILON {
+        scaled.downsample_factor = DownsampleFactor::new(&factor);
+        if empty_placer.can_place(&scaled) {
+            return Some(factor);
+        }
+        factor *= 0.99;
+    }
+    None
+}
+
 pub struct AtlasPacker {
     textures: HashMap<PolygonID, PolygonMappedTexture>,
+    // Group keys of the polygons added with a group
+    groups: HashMap<PolygonID, GroupKey>,
     buffer: u32,
+    sort_strategy: ClusterSortStrategy,
+    page_selection: PageSelection,
+    shrink_pages: bool,
+    oversize_policy: OversizePolicy,
+    texel_budget: Option<(u64, BudgetReduction)>,
+    // Polygons closer than this many pixels are merged
+    merge_tolerance: u32,
+    // Clusters are merged while their union is smaller than their summed area times this
+    merge_cost_threshold: Option<f64>,
+    // Clusters whose polygons cover less than this fraction of their box are split
+    min_coverage: Option<f64>,
+    // Confirm bounding box hits with the polygon shapes
+    exact_overlap: bool,
 }
 
 impl Default for AtlasPacker {
     fn default() -> Self {
         Self {
             textures: HashMap::new(),
+            groups: HashMap::new(),
             buffer: 2,
+            sort_strategy: ClusterSortStrategy::default(),
+            page_selection: PageSelection::default(),
+            shrink_pages: false,
+            oversize_policy: OversizePolicy::default(),
+            texel_budget: None,
+            merge_tolerance: 0,
+            merge_cost_threshold: None,
+            min_coverage: None,
+            exact_overlap: false,
         }
     }
 }
@@ -30,8 +384,17 @@ impl Default for AtlasPacker {
 pub(super) struct Cluster {
     pub bounding_texture: ClusterBoundingTexture,
     pub uv_polygons: Vec<(PolygonID, ChildUVPolygon)>,
+    pub group: Option<GroupKey>,
+    pub location: Option<LocationHint>,
+}
+
+impl Cluster {
+    fn min_polygon_id(&self) -> Option<&PolygonID> {
+        self.uv_polygons.iter().map(|(id, _)| id).min()
+    }
 }
 
+#[derive(PartialEq)]
 struct Rectangle {
     index: usize,
     min_x: f32,
@@ -40,6 +403,120 @@ struct Rectangle {
     max_y: f32,
 }
 
+// Pair of clusters that `merge_by_cost` may merge; the cheapest comes first out of a max-heap
+struct MergeCandidate {
+    cost: f64,
+    pair: (usize, usize),
+    // Generations of the two roots when the candidate was found
+    generations: (u32, u32),
+}
+
+impl PartialEq for MergeCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MergeCandidate {}
+
+impl PartialOrd for MergeCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MergeCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.pair.cmp(&self.pair))
+    }
+}
+
+// Number of sample points per side used to estimate the coverage of a cluster
+const COVERAGE_SAMPLES: u32 = 64;
+
+fn union_bbox(textures: &[&PolygonMappedTexture]) -> (u32, u32, u32, u32) {
+    textures.iter().map(|texture| texture.bbox()).fold(
+        (u32::MAX, u32::MAX, 0, 0),
+        |(min_x, min_y, max_x, max_y), bbox| {
+            (
+                min_x.min(bbox.0),
+                min_y.min(bbox.1),
+                max_x.max(bbox.2),
+                max_y.max(bbox.3),
+            )
+        },
+    )
+}
+
+// Fraction of the union bounding box covered by the polygons, sampled on a grid
+fn coverage(textures: &[&PolygonMappedTexture]) -> f64 {
+    let (min_x, min_y, max_x, max_y) = union_bbox(textures);
+    let (width, height) = ((max_x - min_x) as f64, (max_y - min_y) as f64);
+    if width == 0.0 || height == 0.0 {
+        return 1.0;
+    }
+    let (step_x, step_y) = (
+        width / COVERAGE_SAMPLES as f64,
+        height / COVERAGE_SAMPLES as f64,
+    );
+    // Index range of the sample points within [min, max] on one axis
+    let sample_range = |min: u32, max: u32, origin: u32, step: f64| {
+        let first = (((min - origin) as f64 / step) - 0.5).ceil().max(0.0) as u32;
+        let last = (((max - origin) as f64 / step) - 0.5)
+            .floor()
+            .min(COVERAGE_SAMPLES as f64 - 1.0);
+        first..(last + 1.0).max(0.0) as u32
+    };
+
+    let mut covered = vec![false; (COVERAGE_SAMPLES * COVERAGE_SAMPLES) as usize];
+    for texture in textures {
+        if texture.pixel_coords.len() < 3 {
+            continue;
+        }
+        let polygon = texture
+            .pixel_coords
+            .iter()
+            .map(|&(x, y)| (x as f64, y as f64))
+            .collect::<Vec<_>>();
+        let (polygon_min_x, polygon_min_y, polygon_max_x, polygon_max_y) = texture.bbox();
+        for sample_y in sample_range(polygon_min_y, polygon_max_y, min_y, step_y) {
+            for sample_x in sample_range(polygon_min_x, polygon_max_x, min_x, step_x) {
+                let index = (sample_y * COVERAGE_SAMPLES + sample_x) as usize;
+                let point = (
+                    min_x as f64 + (sample_x as f64 + 0.5) * step_x,
+                    min_y as f64 + (sample_y as f64 + 0.5) * step_y,
+                );
+                if !covered[index] && is_point_inside_polygon(point, &polygon) {
+                    covered[index] = true;
+                }
+            }
+        }
+    }
+    covered.iter().filter(|&&covered| covered).count() as f64 / covered.len() as f64
+}
+
+impl Rectangle {
+    // Rectangle of a pixel bounding box, grown by `margin` on every side
+    fn new(index: usize, bbox: (u32, u32, u32, u32), margin: u32) -> Self {
+        Self::with_margins(index, bbox, (margin, margin))
+    }
+
+    // Rectangle of a pixel bounding box, grown by separate horizontal and vertical margins
+    fn with_margins(index: usize, bbox: (u32, u32, u32, u32), margins: (u32, u32)) -> Self {
+        let (min_x, min_y, max_x, max_y) = bbox;
+        Rectangle {
+            index,
+            min_x: min_x as f32 - margins.0 as f32,
+            min_y: min_y as f32 - margins.1 as f32,
+            max_x: max_x as f32 + margins.0 as f32,
+            max_y: max_y as f32 + margins.1 as f32,
+        }
+    }
+}
+
 impl RTreeObject for Rectangle {
     type Envelope = AABB<[f32; 2]>;
 
@@ -49,168 +526,1278 @@ impl RTreeObject for Rectangle {
 }
 
 impl AtlasPacker {
+    pub fn with_sort_strategy(mut self, sort_strategy: ClusterSortStrategy) -> Self {
+        self.sort_strategy = sort_strategy;
+        self
+    }
+
+    pub fn with_page_selection(mut self, page_selection: PageSelection) -> Self {
+        self.page_selection = page_selection;
+        self
+    }
+
+    // Shrink each page to the smallest size that holds its textures, rounded up with the
+    // placer's `TexturePlacerConfig::size_rounding`
+    pub fn with_page_shrinking(mut self, shrink_pages: bool) -> Self {
+        self.shrink_pages = shrink_pages;
+        self
+    }
+
+    pub fn with_oversize_policy(mut self, oversize_policy: OversizePolicy) -> Self {
+        self.oversize_policy = oversize_policy;
+        self
+    }
+
+    /// Merge polygons of the same source image whose bounding boxes are at most `pixels` apart
+    pub fn with_merge_tolerance(mut self, pixels: u32) -> Self {
+        self.merge_tolerance = pixels;
+        self
+    }
+
+    /// Merge two clusters of the same source image when the buffered area of their union is less
+    /// than the sum of their buffered areas times `threshold` (e.g. 1.1 accepts 10% more texels
+    /// to save the buffer border and the placement overhead of one cluster). The cheapest pair
+    /// is merged first. Higher thresholds look for partners farther away, so they cost more.
+    pub fn with_merge_cost_threshold(mut self, threshold: f64) -> Self {
+        self.merge_cost_threshold = Some(threshold);
+        self
+    }
+
+    /// Merge polygons whose bounding boxes overlap (within the merge tolerance) only when the
+    /// polygons themselves, grown by the buffer and the merge tolerance, overlap too (e.g.
+    /// diagonal roof strips). This never merges more than the bounding box test alone.
+    pub fn with_exact_overlap(mut self, exact_overlap: bool) -> Self {
+        self.exact_overlap = exact_overlap;
+        self
+    }
+
+    /// Split clusters whose polygons cover less than `min_coverage` (0~1) of their bounding box,
+    /// as long as the parts need less buffered area than the whole. Texels shared by polygons in
+    /// different parts are duplicated.
+    pub fn with_sparse_split(mut self, min_coverage: f64) -> Self {
+        if (0.0..=1.0).contains(&min_coverage) {
+            self.min_coverage = Some(min_coverage);
+            self
+        } else {
+            panic!("The minimum coverage must be between 0 and 1.")
+        }
+    }
+
+    /// Reduce the downsample factors of the clusters until their total buffered area is at most
+    /// `texels`, or as close to it as one texel per cluster allows
+    pub fn with_texel_budget(mut self, texels: u64, reduction: BudgetReduction) -> Self {
+        self.texel_budget = Some((texels, reduction));
+        self
+    }
+
     pub fn add_texture(&mut self, polygon_id: PolygonID, texture: PolygonMappedTexture) {
+        self.groups.remove(&polygon_id);
         self.textures.insert(polygon_id, texture);
     }
 
-    fn create_clusters(&self) -> HashMap<ClusterID, Cluster> {
-        let polygon_ids: Vec<PolygonID> = self.textures.keys().cloned().collect();
+    /// Add a texture that is only clustered and paged with the textures of the same group
+    pub fn add_texture_with_group(
+        &mut self,
+        polygon_id: PolygonID,
+        texture: PolygonMappedTexture,
+        group: GroupKey,
+    ) {
+        self.groups.insert(polygon_id.clone(), group);
+        self.textures.insert(polygon_id, texture);
+    }
+
+    /// Polygon IDs of each cluster that `pack` would place, after merging and splitting
+    pub fn clusters(&self) -> Vec<Vec<PolygonID>> {
+        self.create_clusters()
+            .into_values()
+            .map(|cluster| {
+                cluster
+                    .uv_polygons
+                    .into_iter()
+                    .map(|(polygon_id, _)| polygon_id)
+                    .collect()
+            })
+            .collect()
+    }
 
-        let mut rtree = RTree::new();
-        let mut disjoint_set = DisjointSet::new(polygon_ids.len());
+    fn create_clusters(&self) -> HashMap<ClusterID, Cluster> {
+        let polygon_ids: Vec<&PolygonID> = self.textures.keys().collect();
 
+        // Only polygons of the same texture and group can be merged, so each partition is
+        // clustered on its own
+        let mut partitions: HashMap<(&Path, Option<&GroupKey>), Vec<usize>> = HashMap::new();
         for (i, polygon_id) in polygon_ids.iter().enumerate() {
-            let texture = self.textures.get(polygon_id).unwrap();
-            let (min_x, min_y, max_x, max_y) = texture.bbox();
-            let texture_with_index = Rectangle {
-                index: i,
-                min_x: min_x as f32,
-                min_y: min_y as f32,
-                max_x: max_x as f32,
-                max_y: max_y as f32,
-            };
-            rtree.insert(texture_with_index);
+            let texture = &self.textures[*polygon_id];
+            partitions
+                .entry((texture.image_path.as_path(), self.groups.get(*polygon_id)))
+                .or_default()
+                .push(i);
         }
-        for (i, polygon_id) in polygon_ids.iter().enumerate() {
-            let texture = self.textures.get(polygon_id).unwrap();
-            let bbox = AABB::from_corners(
-                [texture.bbox().0 as f32, texture.bbox().1 as f32],
-                [texture.bbox().2 as f32, texture.bbox().3 as f32],
-            );
 
-            // Only items with the same texture and overlapping areas will be searched
-            let hit = rtree
-                .locate_in_envelope_intersecting(&bbox)
-                .filter(|target| {
-                    let target_texture = self.textures.get(&polygon_ids[target.index]).unwrap();
-                    texture.image_path == target_texture.image_path
-                });
+        partitions
+            .into_values()
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .flat_map_iter(|indices| self.cluster_partition(&polygon_ids, &indices))
+            .flat_map_iter(|members| match self.min_coverage {
+                Some(min_coverage) => self.split_sparse(&polygon_ids, members, min_coverage),
+                None => vec![members],
+            })
+            .filter_map(|members| {
+                // The smallest index in the cluster makes the ID unique
+                let cluster_id = members.iter().min()?.to_string();
+                let member_ids = members
+                    .iter()
+                    .map(|&i| polygon_ids[i].clone())
+                    .collect::<Vec<_>>();
+                Some((cluster_id, self.build_cluster(&member_ids)?))
+            })
+            .collect()
+    }
+
+    // Group the polygons (indices into `polygon_ids`) whose bounding boxes, or shapes with
+    // `exact_overlap`, overlap
+    fn cluster_partition(&self, polygon_ids: &[&PolygonID], indices: &[usize]) -> Vec<Vec<usize>> {
+        let bboxes = indices
+            .iter()
+            .map(|&i| self.textures[polygon_ids[i]].bbox())
+            .collect::<Vec<_>>();
+        let rtree = RTree::bulk_load(
+            bboxes
+                .iter()
+                .enumerate()
+                .map(|(index, bbox)| Rectangle::new(index, *bbox, 0))
+                .collect(),
+        );
 
-            for j in hit {
-                if i < j.index {
-                    disjoint_set.unite(i, j.index);
+        // The exact test only confirms the bounding box hits. Polygons whose buffered texels touch
+        // share texels, so it grows them by the buffers as well.
+        let polygon_margin = self.merge_tolerance + self.buffer * 2;
+        let texture = |index: usize| &self.textures[polygon_ids[indices[index]]];
+
+        let mut disjoint_set = DisjointSet::new(indices.len());
+        for (index, bbox) in bboxes.iter().enumerate() {
+            let envelope = Rectangle::new(index, *bbox, self.merge_tolerance).envelope();
+            for target in rtree.locate_in_envelope_intersecting(&envelope) {
+                if index < target.index
+                    && (!self.exact_overlap
+                        || disjoint_set.root(index) != disjoint_set.root(target.index)
+                            && texture(index)
+                                .polygon_overlaps(texture(target.index), polygon_margin))
+                {
+                    disjoint_set.unite(index, target.index);
                 }
             }
         }
-        disjoint_set.compress();
+        if let Some(threshold) = self.merge_cost_threshold {
+            self.merge_by_cost(&mut disjoint_set, &bboxes, threshold);
+        }
 
-        let clustered_polygon_ids: HashMap<ClusterID, Vec<PolygonID>> = {
-            let mut clustered_polygon_ids = HashMap::new();
-            for (i, polygon_id) in polygon_ids.iter().enumerate() {
-                let cluster_id = disjoint_set.root(i).to_string();
-                clustered_polygon_ids
-                    .entry(cluster_id)
-                    .or_insert_with(Vec::new)
-                    .push(polygon_id.clone());
-            }
-            clustered_polygon_ids
+        let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (index, &i) in indices.iter().enumerate() {
+            clusters
+                .entry(disjoint_set.root(index))
+                .or_default()
+                .push(i);
+        }
+        clusters.into_values().collect()
+    }
+
+    // Bisect a sparse cluster (indices into `polygon_ids`) along the longer side of its box, and
+    // the parts again, while that lowers the buffered area
+    fn split_sparse(
+        &self,
+        polygon_ids: &[&PolygonID],
+        mut members: Vec<usize>,
+        min_coverage: f64,
+    ) -> Vec<Vec<usize>> {
+        let textures = |members: &[usize]| {
+            members
+                .iter()
+                .map(|&i| &self.textures[polygon_ids[i]])
+                .collect::<Vec<_>>()
+        };
+        let buffered_area = |members: &[usize]| {
+            let (min_x, min_y, max_x, max_y) = union_bbox(&textures(members));
+            (max_x - min_x + self.buffer * 2) as u64 * (max_y - min_y + self.buffer * 2) as u64
         };
 
-        let cluster_map: HashMap<ClusterID, Cluster> = clustered_polygon_ids
-            .iter()
-            .filter_map(|(cluster_id, polygon_ids)| {
-                let bounding_texture = polygon_ids.iter().fold(
-                    None,
-                    |acc: Option<ClusterBoundingTexture>, polygon_id| {
-                        let texture = self.textures.get(polygon_id).unwrap();
-                        match acc {
-                            Some(bounding_texture) => bounding_texture.expand(texture),
-                            None => Some(ClusterBoundingTexture::new(texture, self.buffer)),
-                        }
-                    },
-                )?;
+        if members.len() < 2 || coverage(&textures(&members)) >= min_coverage {
+            return vec![members];
+        }
 
-                let uv_polygons = polygon_ids
-                    .iter()
-                    .map(|polygon_id| {
-                        let texture = self.textures.get(polygon_id).unwrap();
-                        (polygon_id.clone(), bounding_texture.get_child(texture))
+        let whole_area = buffered_area(&members);
+        let second_half = self.bisect(&mut members, |&i| polygon_ids[i]);
+        if buffered_area(&members) + buffered_area(&second_half) >= whole_area {
+            members.extend(second_half);
+            return vec![members];
+        }
+
+        let mut parts = self.split_sparse(polygon_ids, members, min_coverage);
+        parts.extend(self.split_sparse(polygon_ids, second_half, min_coverage));
+        parts
+    }
+
+    // Greedily merge the pair of clusters with the lowest cost (buffered union area over the
+    // summed buffered areas) while it is below the threshold. Each merge only revisits the
+    // neighbours of the merged cluster, found through an R-tree of the reach of every cluster
+    fn merge_by_cost(
+        &self,
+        disjoint_set: &mut DisjointSet,
+        bboxes: &[(u32, u32, u32, u32)],
+        threshold: f64,
+    ) {
+        let union = |a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)| {
+            (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))
+        };
+        let buffered_size = |bbox: (u32, u32, u32, u32)| {
+            (
+                (bbox.2 - bbox.0 + self.buffer * 2) as f64,
+                (bbox.3 - bbox.1 + self.buffer * 2) as f64,
+            )
+        };
+        let area = |bbox: (u32, u32, u32, u32)| {
+            let (width, height) = buffered_size(bbox);
+            width * height
+        };
+        // Two clusters at a horizontal gap `d` make a union of at least
+        // `(width(a) + width(b) - 2 * buffer + d) * max(height(a), height(b))`, so they can only
+        // pass the threshold while `d < (threshold - 1) * (width(a) + width(b)) + 2 * buffer`
+        // (likewise vertically). Growing each box by its share of that bound makes the grown
+        // boxes of every mergeable pair intersect.
+        let reach_area = |root: usize, bbox: (u32, u32, u32, u32)| {
+            let (width, height) = buffered_size(bbox);
+            let reach = |side: f64| {
+                ((threshold - 1.0) * side + self.buffer as f64)
+                    .max(0.0)
+                    .ceil()
+                    .min(u32::MAX as f64) as u32
+            };
+            Rectangle::with_margins(root, bbox, (reach(width), reach(height)))
+        };
+        let find_candidates = |root: usize,
+                               bbox: (u32, u32, u32, u32),
+                               rtree: &RTree<Rectangle>,
+                               cluster_bboxes: &HashMap<usize, (u32, u32, u32, u32)>,
+                               generations: &[u32]| {
+            rtree
+                .locate_in_envelope_intersecting(&reach_area(root, bbox).envelope())
+                .filter(|target| target.index != root)
+                .filter_map(|target| {
+                    let target_bbox = cluster_bboxes[&target.index];
+                    let cost = area(union(bbox, target_bbox)) / (area(bbox) + area(target_bbox));
+                    (cost < threshold).then(|| MergeCandidate {
+                        cost,
+                        pair: (root.min(target.index), root.max(target.index)),
+                        generations: if root < target.index {
+                            (generations[root], generations[target.index])
+                        } else {
+                            (generations[target.index], generations[root])
+                        },
                     })
-                    .collect::<Vec<(PolygonID, ChildUVPolygon)>>();
+                })
+                .collect::<Vec<_>>()
+        };
 
-                Some((
-                    cluster_id.clone(),
-                    Cluster {
-                        bounding_texture,
-                        uv_polygons,
-                    },
-                ))
+        let mut cluster_bboxes: HashMap<usize, (u32, u32, u32, u32)> = HashMap::new();
+        for (index, bbox) in bboxes.iter().enumerate() {
+            let root = disjoint_set.root(index);
+            let merged = cluster_bboxes
+                .get(&root)
+                .map_or(*bbox, |b| union(*b, *bbox));
+            cluster_bboxes.insert(root, merged);
+        }
+        // Bumped whenever the box of a root changes, to skip outdated candidates
+        let mut generations = vec![0u32; bboxes.len()];
+        let mut rtree = RTree::bulk_load(
+            cluster_bboxes
+                .iter()
+                .map(|(&root, &bbox)| reach_area(root, bbox))
+                .collect(),
+        );
+        let mut candidates = BinaryHeap::new();
+        for (&root, &bbox) in cluster_bboxes.iter() {
+            candidates.extend(
+                find_candidates(root, bbox, &rtree, &cluster_bboxes, &generations)
+                    .into_iter()
+                    .filter(|candidate| candidate.pair.0 == root),
+            );
+        }
+
+        while let Some(MergeCandidate {
+            pair: (a, b),
+            generations: (generation_a, generation_b),
+            ..
+        }) = candidates.pop()
+        {
+            if generations[a] != generation_a || generations[b] != generation_b {
+                continue;
+            }
+            let (Some(bbox_a), Some(bbox_b)) =
+                (cluster_bboxes.remove(&a), cluster_bboxes.remove(&b))
+            else {
+                unreachable!("Candidates of merged clusters are outdated");
+            };
+            rtree.remove(&reach_area(a, bbox_a));
+            rtree.remove(&reach_area(b, bbox_b));
+
+            disjoint_set.unite(a, b);
+            let root = disjoint_set.root(a);
+            let bbox = union(bbox_a, bbox_b);
+            generations[a] += 1;
+            generations[b] += 1;
+            candidates.extend(find_candidates(
+                root,
+                bbox,
+                &rtree,
+                &cluster_bboxes,
+                &generations,
+            ));
+            cluster_bboxes.insert(root, bbox);
+            rtree.insert(reach_area(root, bbox));
+        }
+    }
+
+    fn build_cluster(&self, polygon_ids: &[PolygonID]) -> Option<Cluster> {
+        let bounding_texture =
+            polygon_ids
+                .iter()
+                .fold(None, |acc: Option<ClusterBoundingTexture>, polygon_id| {
+                    let texture = self.textures.get(polygon_id).unwrap();
+                    match acc {
+                        Some(bounding_texture) => bounding_texture.expand(texture),
+                        None => Some(ClusterBoundingTexture::new(texture, self.buffer)),
+                    }
+                })?;
+
+        let uv_polygons = polygon_ids
+            .iter()
+            .map(|polygon_id| {
+                let texture = self.textures.get(polygon_id).unwrap();
+                (polygon_id.clone(), bounding_texture.get_child(texture))
             })
-            .collect::<HashMap<_, _>>();
+            .collect::<Vec<(PolygonID, ChildUVPolygon)>>();
+
+        // Mean of the world positions, or else the smallest tile key
+        let hints = polygon_ids
+            .iter()
+            .filter_map(|polygon_id| self.textures[polygon_id].location);
+        let positions = hints
+            .clone()
+            .filter_map(|hint| match hint {
+                LocationHint::Position(x, y) => Some((x, y)),
+                LocationHint::TileKey(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let location = if positions.is_empty() {
+            hints
+                .filter_map(|hint| match hint {
+                    LocationHint::TileKey(key) => Some(key),
+                    LocationHint::Position(..) => None,
+                })
+                .min()
+                .map(LocationHint::TileKey)
+        } else {
+            let count = positions.len() as f64;
+            let (sum_x, sum_y) = positions
+                .iter()
+                .fold((0.0, 0.0), |(sum_x, sum_y), (x, y)| (sum_x + x, sum_y + y));
+            Some(LocationHint::Position(sum_x / count, sum_y / count))
+        };
 
-        cluster_map
+        Some(Cluster {
+            bounding_texture,
+            uv_polygons,
+            group: self.groups.get(&polygon_ids[0]).cloned(),
+            location,
+        })
     }
 
-    pub fn pack<P: TexturePlacer>(self, mut placer: P) -> PackedAtlasProvider {
-        let mut current_atlas: Atlas = Vec::new();
-        let mut atlases: HashMap<AtlasID, Atlas> = HashMap::new();
+    // Split the polygons in half at the median center of their boxes along the longer side of the
+    // union box, and return the second half. Ties are broken by polygon ID.
+    fn bisect<'a, T>(
+        &'a self,
+        members: &mut Vec<T>,
+        polygon_id: impl Fn(&T) -> &'a PolygonID,
+    ) -> Vec<T> {
+        let textures = members
+            .iter()
+            .map(|member| &self.textures[polygon_id(member)])
+            .collect::<Vec<_>>();
+        let (min_x, min_y, max_x, max_y) = union_bbox(&textures);
+        let along_x = max_x - min_x >= max_y - min_y;
+        members.sort_by_cached_key(|member| {
+            let (min_x, min_y, max_x, max_y) = self.textures[polygon_id(member)].bbox();
+            let center = if along_x {
+                min_x + max_x
+            } else {
+                min_y + max_y
+            };
+            (center, polygon_id(member).clone())
+        });
+        members.split_off(members.len() / 2)
+    }
+
+    // Bisect the polygons of a cluster along its longer side until every part fits an empty page.
+    // Texels shared by polygons in different parts are duplicated.
+    fn split_cluster<P: TexturePlacer>(
+        &self,
+        polygon_ids: Vec<PolygonID>,
+        empty_placer: &P,
+    ) -> Vec<Cluster> {
+        let cluster = self.build_cluster(&polygon_ids).unwrap();
+        if polygon_ids.len() == 1 || empty_placer.can_place(&cluster.bounding_texture) {
+            return vec![cluster];
+        }
+
+        let mut first_half = polygon_ids.iter().collect::<Vec<_>>();
+        let second_half = self.bisect(&mut first_half, |polygon_id| *polygon_id);
+        let owned = |half: Vec<&PolygonID>| half.into_iter().cloned().collect();
+
+        let mut parts = self.split_cluster(owned(first_half), empty_placer);
+        parts.extend(self.split_cluster(owned(second_half), empty_placer));
+        parts
+    }
+
+    /// Pack the textures with `placer` on its first page. Keeping several pages open (see
+    /// `with_page_selection`) needs a placer that implements `TexturePlacer::new_page`; other
+    /// placers fill one page at a time.
+    pub fn pack<P: TexturePlacer>(self, placer: P) -> PackedAtlasProvider {
+        self.pack_with(
+            self.create_clusters(),
+            placer,
+            &self.sort_strategy,
+            &self.page_selection,
+        )
+    }
 
+    /// Pack the same clusters with every candidate strategy in parallel and keep the result
+    /// with the fewest pages, then the highest occupancy (the earlier candidate wins ties).
+    /// The scores of all candidates are returned in the given order.
+    ///
+    /// Panics if `candidates` is empty.
+    pub fn pack_best(
+        self,
+        candidates: Vec<PackingCandidate>,
+    ) -> (PackedAtlasProvider, Vec<CandidateScore>) {
+        assert!(!candidates.is_empty(), "At least one candidate is required");
         let clusters = self.create_clusters();
-        let mut placed_uv_polygon_map: HashMap<PolygonID, PlacedUVPolygon> = HashMap::new();
-        for (cluster_id, cluster) in clusters.iter() {
-            if !placer.can_place(&cluster.bounding_texture) {
-                let current_atlas_id = atlases.len();
-                atlases.insert(current_atlas_id, current_atlas.clone());
-                current_atlas.clear();
-                placer.reset_param();
+        let results = candidates
+            .into_par_iter()
+            .map(|candidate| {
+                let packed = self.pack_with(
+                    clusters.clone(),
+                    candidate.placer,
+                    &candidate.sort_strategy,
+                    &candidate.page_selection,
+                );
+                let score = CandidateScore {
+                    name: candidate.name,
+                    page_count: packed.atlases.len(),
+                    occupancy: packed.occupancy(),
+                };
+                (packed, score)
+            })
+            .collect::<Vec<_>>();
+
+        let best = results
+            .iter()
+            .enumerate()
+            .min_by(|(_, (_, a)), (_, (_, b))| {
+                a.page_count
+                    .cmp(&b.page_count)
+                    .then(b.occupancy.total_cmp(&a.occupancy))
+            })
+            .map(|(index, _)| index)
+            .unwrap();
+        let scores = results.iter().map(|(_, score)| score.clone()).collect();
+        let (packed, _) = results.into_iter().nth(best).unwrap();
+        (packed, scores)
+    }
+
+    /// Search the largest uniform scale, applied on top of the downsample factor of every texture,
+    /// with which all clusters fit into at most `max_pages` pages.
+    /// Returns the layout and the chosen scale, or `None` if no scale tried is small enough.
+    pub fn pack_to_page_count<P: TexturePlacer + Clone>(
+        self,
+        placer: P,
+        max_pages: usize,
+    ) -> Option<(PackedAtlasProvider, f32)> {
+        let clusters = self.create_clusters();
+        let pack_scaled = |scale: f32| {
+            let scaled = clusters
+                .iter()
+                .map(|(cluster_id, cluster)| {
+                    let mut cluster = cluster.clone();
+                    let factor = cluster.bounding_texture.downsample_factor.value() * scale;
+                    cluster.bounding_texture.downsample_factor = DownsampleFactor::new(&factor);
+                    (cluster_id.clone(), cluster)
+                })
+                .collect();
+            self.pack_with(
+                scaled,
+                placer.clone(),
+                &self.sort_strategy,
+                &self.page_selection,
+            )
+        };
+
+        let packed = pack_scaled(1.0);
+        if packed.atlases.len() <= max_pages {
+            return Some((packed, 1.0));
+        }
+
+        // Binary search; the page count is not strictly monotonic in the scale, so the result is
+        // the largest fitting scale found rather than a guaranteed optimum
+        let mut best = None;
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..SCALE_SEARCH_STEPS {
+            let scale = (low + high) / 2.0;
+            let packed = pack_scaled(scale);
+            if packed.atlases.len() <= max_pages {
+                low = scale;
+                best = Some((packed, scale));
+            } else {
+                high = scale;
             }
+        }
+        best
+    }
 
-            let current_atlas_id = atlases.len();
+    /// Pack the textures of this packer into the free space of an already packed result, opening
+    /// new pages only when needed. The existing placements do not move, and are reserved on a
+    /// copy of `placer`. Dedicated pages and pages of another size than the configured one are
+    /// not filled. Returns the IDs of the pages that changed, in ascending order.
+    ///
+    /// Fails without changing `packed` if some polygon IDs are already in it, or if there are
+    /// pages to fill and `placer` does not support `TexturePlacer::reserve` (e.g. the shelf
+    /// placer).
+    pub fn pack_into<P: TexturePlacer + Clone + 'static>(
+        self,
+        packed: &mut PackedAtlasProvider,
+        placer: P,
+    ) -> Result<Vec<AtlasID>, PackIntoError> {
+        let mut already_packed = self
+            .textures
+            .keys()
+            .filter(|polygon_id| packed.placed_uv_polygon_map.contains_key(*polygon_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !already_packed.is_empty() {
+            already_packed.sort();
+            return Err(PackIntoError::AlreadyPacked(already_packed));
+        }
 
-            let (placed_texture, placed_uv_polygons) = placer.place_texture(
-                cluster.bounding_texture.clone(),
-                cluster.uv_polygons.clone(),
-                cluster_id.clone(),
-                current_atlas_id,
-            );
+        let mut empty_placer = placer.into_box();
+        empty_placer.reset_param();
+        let config = empty_placer.config().clone();
 
-            current_atlas.push(placed_texture.clone());
+        // Cluster IDs restart from scratch in every packer, so clashing ones get a suffix
+        let mut prepared = self.prepare_clusters(self.create_clusters(), &empty_placer);
+        let (clusters, mut dedicated) = (
+            std::mem::take(&mut prepared.clusters),
+            std::mem::take(&mut prepared.dedicated),
+        );
+        for (cluster_id, cluster) in clusters {
+            let mut unique_id = cluster_id.clone();
+            let mut suffix = 0;
+            while packed.clusters.contains_key(&unique_id) {
+                suffix += 1;
+                unique_id = format!("{}~{}", cluster_id, suffix);
+            }
+            if dedicated.remove(&cluster_id) {
+                prepared.dedicated.insert(unique_id.clone());
+            }
+            prepared.clusters.insert(unique_id, cluster);
+        }
 
-            let polygon_ids = cluster
-                .uv_polygons
-                .iter()
-                .map(|(id, _)| id)
-                .collect::<Vec<_>>();
+        // Keeping only the last page open would leave the free space of the others unused
+        let page_selection = match self.page_selection {
+            PageSelection::Single => PageSelection::FirstFit,
+            page_selection => page_selection,
+        };
+        let first_new_page = packed
+            .atlases
+            .keys()
+            .max()
+            .map_or(0, |atlas_id| atlas_id + 1);
+        let dedicated_pages = packed
+            .oversized
+            .values()
+            .filter_map(|outcome| match outcome {
+                OversizeOutcome::DedicatedPage(atlas_id) => Some(*atlas_id),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let mut open_pages = packed
+            .atlases
+            .keys()
+            .filter(|atlas_id| {
+                !dedicated_pages.contains(*atlas_id)
+                    && packed.page_sizes.get(*atlas_id) == Some(&(config.width(), config.height()))
+            })
+            .map(|&atlas_id| (atlas_id, packed.get_page_group(atlas_id).cloned()))
+            .collect::<Vec<_>>();
+        open_pages.sort();
 
-            for (polygon_id, placed_uv_polygon) in polygon_ids.iter().zip(placed_uv_polygons) {
-                if let Some(placed_uv_polygon) = placed_uv_polygon {
-                    placed_uv_polygon_map.insert((*polygon_id).clone(), placed_uv_polygon.clone());
-                }
+        let mut reserved_placers = HashMap::new();
+        for (atlas_id, _) in &open_pages {
+            let mut placer = empty_placer.clone();
+            if !packed.atlases[atlas_id]
+                .iter()
+                .all(|placed| placer.reserve(placed))
+            {
+                return Err(PackIntoError::ReserveUnsupported);
             }
+            reserved_placers.insert(*atlas_id, placer);
         }
 
-        // treat the last atlas
-        if !current_atlas.is_empty() {
-            let current_atlas_id = atlases.len();
+        let mut changed = Vec::new();
+        let mut next_page = first_new_page;
+        let order = self.sort_strategy.sort(&prepared.clusters);
+        for group in split_by_group(order) {
+            let group_key = &group[0].1.group;
+            let page_ids = open_pages
+                .iter()
+                .filter(|(_, page_group)| page_group == group_key)
+                .map(|(atlas_id, _)| *atlas_id)
+                .collect::<Vec<_>>();
+            let pages = page_ids
+                .iter()
+                .map(|atlas_id| {
+                    let mut page = Page::new(reserved_placers.remove(atlas_id).unwrap());
+                    page.used_area = packed.atlases[atlas_id]
+                        .iter()
+                        .map(|placed| placed.width as u64 * placed.height as u64)
+                        .sum();
+                    page
+                })
+                .collect();
+
+            let placed = Self::place_clusters(
+                &group,
+                pages,
+                &mut Some(empty_placer.clone()),
+                &config,
+                &page_selection,
+                &prepared.dedicated,
+            );
 
-            atlases.insert(current_atlas_id, current_atlas.clone());
-            current_atlas.clear();
+            // Page indices of this group to atlas IDs
+            let existing_count = page_ids.len();
+            let atlas_id_of = |index: AtlasID| match page_ids.get(index) {
+                Some(atlas_id) => *atlas_id,
+                None => next_page + index - existing_count,
+            };
+            let new_page_count = placed.pages.len() - existing_count;
+            for (index, (mut atlas, size)) in placed.pages.into_iter().enumerate() {
+                if atlas.is_empty() {
+                    continue;
+                }
+                let atlas_id = atlas_id_of(index);
+                for placed_texture in atlas.iter_mut() {
+                    placed_texture.atlas_id = atlas_id;
+                }
+                changed.push(atlas_id);
+                packed.atlases.entry(atlas_id).or_default().extend(atlas);
+                packed.page_sizes.entry(atlas_id).or_insert(size);
+            }
+            for (polygon_id, mut placed_uv_polygon) in placed.placed_uv_polygon_map {
+                placed_uv_polygon.atlas_id = atlas_id_of(placed_uv_polygon.atlas_id);
+                packed
+                    .placed_uv_polygon_map
+                    .insert(polygon_id, placed_uv_polygon);
+            }
+            for (polygon_id, index) in placed.dedicated_pages {
+                prepared.oversized.insert(
+                    polygon_id,
+                    OversizeOutcome::DedicatedPage(atlas_id_of(index)),
+                );
+            }
+            next_page += new_page_count;
         }
+        changed.sort();
 
-        PackedAtlasProvider {
-            clusters,
-            atlases,
-            placed_uv_polygon_map,
+        packed.oversized.extend(prepared.oversized);
+        packed.clusters.extend(prepared.clusters);
+        if self.shrink_pages {
+            packed.shrink_pages(&config, first_new_page);
+            packed.pages_shrunk = true;
         }
+        Ok(changed)
     }
-}
 
-pub struct PackedAtlasProvider {
-    atlases: HashMap<AtlasID, Atlas>,
-    clusters: HashMap<ClusterID, Cluster>,
-    placed_uv_polygon_map: HashMap<PolygonID, PlacedUVPolygon>,
-}
+    // The given placer fills the first page. Placers that cannot open another page (see
+    // `TexturePlacer::new_page`) are checked against oversized clusters as they are.
+    fn pack_with<P: TexturePlacer>(
+        &self,
+        clusters: HashMap<ClusterID, Cluster>,
+        placer: P,
+        sort_strategy: &ClusterSortStrategy,
+        page_selection: &PageSelection,
+    ) -> PackedAtlasProvider {
+        let config = placer.config().clone();
+        let empty_placer = placer.new_page();
+        let prepared = self.prepare_clusters(clusters, empty_placer.as_ref().unwrap_or(&placer));
+
+        let mut spare = Some(placer);
+        let placed = split_by_group(sort_strategy.sort(&prepared.clusters))
+            .into_iter()
+            .map(|group| {
+                Self::place_clusters(
+                    &group,
+                    Vec::new(),
+                    &mut spare,
+                    &config,
+                    page_selection,
+                    &prepared.dedicated,
+                )
+            })
+            .collect();
+        self.assemble(prepared, placed, &config)
+    }
+
+    /// Split the clusters into independent groups and pack each group on its own rayon task.
+    /// Pages never mix clusters of different groups.
+    ///
+    /// `placer` is only a template: it is reset, and every group starts from a clone of it, so
+    /// unlike `pack`, no placement already in it is kept.
+    pub fn pack_parallel<P: TexturePlacer + Clone>(
+        self,
+        placer: P,
+        grouping: ParallelGrouping,
+    ) -> PackedAtlasProvider {
+        let mut empty_placer = placer;
+        empty_placer.reset_param();
+
+        let prepared = self.prepare_clusters(self.create_clusters(), &empty_placer);
+        let placed = {
+            let groups = split_by_group(self.sort_strategy.sort(&prepared.clusters))
+                .into_iter()
+                .flat_map(|group| grouping.group(group))
+                .collect::<Vec<_>>();
+            groups
+                .par_iter()
+                .map(|group| {
+                    let order = self.sort_strategy.sort(group.iter().copied());
+                    Self::place_clusters(
+                        &order,
+                        Vec::new(),
+                        &mut Some(empty_placer.clone()),
+                        empty_placer.config(),
+                        &self.page_selection,
+                        &prepared.dedicated,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        self.assemble(prepared, placed, empty_placer.config())
+    }
+
+    // Handle the clusters that do not fit an empty page according to the oversize policy
+    fn prepare_clusters<P: TexturePlacer>(
+        &self,
+        created: HashMap<ClusterID, Cluster>,
+        empty_placer: &P,
+    ) -> PreparedClusters {
+        let mut created = created;
+        self.apply_texel_budget(&mut created);
+
+        let mut clusters: HashMap<ClusterID, Cluster> = HashMap::new();
+        let mut oversized: HashMap<PolygonID, OversizeOutcome> = HashMap::new();
+        let mut dedicated: HashSet<ClusterID> = HashSet::new();
+        for (cluster_id, cluster) in created {
+            if empty_placer.can_place(&cluster.bounding_texture) {
+                clusters.insert(cluster_id, cluster);
+                continue;
+            }
+            let parts = match self.oversize_policy {
+                OversizePolicy::Downscale => vec![(cluster_id, cluster, None)],
+                // The page ID is filled in when the cluster is placed
+                OversizePolicy::DedicatedPage => {
+                    dedicated.insert(cluster_id.clone());
+                    clusters.insert(cluster_id, cluster);
+                    continue;
+                }
+                OversizePolicy::Split => {
+                    let polygon_ids = cluster.uv_polygons.into_iter().map(|(id, _)| id).collect();
+                    self.split_cluster(polygon_ids, empty_placer)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, part)| {
+                            (
+                                format!("{}-{}", cluster_id, i),
+                                part,
+                                Some(OversizeOutcome::Split),
+                            )
+                        })
+                        .collect()
+                }
+                OversizePolicy::Skip => {
+                    for (polygon_id, _) in &cluster.uv_polygons {
+                        oversized.insert(polygon_id.clone(), OversizeOutcome::Skipped);
+                    }
+                    continue;
+                }
+            };
+            for (cluster_id, mut cluster, outcome) in parts {
+                let outcome = match outcome {
+                    Some(outcome) if empty_placer.can_place(&cluster.bounding_texture) => outcome,
+                    _ => match fit_downsample_factor(empty_placer, &cluster.bounding_texture) {
+                        Some(factor) => {
+                            cluster.bounding_texture.downsample_factor =
+                                DownsampleFactor::new(&factor);
+                            OversizeOutcome::Downscaled(factor)
+                        }
+                        None => OversizeOutcome::Skipped,
+                    },
+                };
+                for (polygon_id, _) in &cluster.uv_polygons {
+                    oversized.insert(polygon_id.clone(), outcome.clone());
+                }
+                if outcome != OversizeOutcome::Skipped {
+                    clusters.insert(cluster_id, cluster);
+                }
+            }
+        }
+
+        PreparedClusters {
+            clusters,
+            dedicated,
+            oversized,
+        }
+    }
+
+    // Scale the factor of every cluster by min(1, s * sqrt(weight)), with the largest s that meets
+    // the budget, so that the area kept by each cluster grows with its weight
+    fn apply_texel_budget(&self, clusters: &mut HashMap<ClusterID, Cluster>) {
+        let Some((budget, reduction)) = self.texel_budget else {
+            return;
+        };
+        let mut weighted = clusters
+            .values_mut()
+            .map(|cluster| {
+                let weight = match reduction {
+                    BudgetReduction::Proportional => 1.0,
+                    BudgetReduction::Priority => cluster
+                        .uv_polygons
+                        .iter()
+                        .map(|(polygon_id, _)| self.textures[polygon_id].priority)
+                        .fold(f32::MIN_POSITIVE, f32::max),
+                };
+                let factor = cluster.bounding_texture.downsample_factor.value();
+                (cluster, factor, weight.sqrt())
+            })
+            .collect::<Vec<_>>();
+
+        let scaled_factor =
+            |factor: f32, weight: f32, scale: f32| factor * (scale * weight).min(1.0);
+        let total_area = |weighted: &[(&mut Cluster, f32, f32)], scale: f32| {
+            weighted
+                .iter()
+                .map(|(cluster, factor, weight)| {
+                    let (width, height) = cluster
+                        .bounding_texture
+                        .get_size_with_factor(scaled_factor(*factor, *weight, scale));
+                    width as u64 * height as u64
+                })
+                .sum::<u64>()
+        };
+
+        // Every cluster keeps its own factor at this scale
+        let Some(mut high) = weighted
+            .iter()
+            .map(|(_, _, weight)| 1.0 / weight)
+            .reduce(f32::max)
+        else {
+            return;
+        };
+        if total_area(&weighted, high) <= budget {
+            return;
+        }
+        let mut low = 0.0;
+        for _ in 0..BUDGET_SEARCH_STEPS {
+            let scale = (low + high) / 2.0;
+            if total_area(&weighted, scale) <= budget {
+                low = scale;
+            } else {
+                high = scale;
+            }
+        }
+        for (cluster, factor, weight) in weighted.iter_mut() {
+            // Below one texel per cluster, every cluster still keeps at least 1px
+            let (_, _, width, height) = cluster.bounding_texture.get_buffered_geometry();
+            let min_factor = (1.0 / width.min(height).max(1) as f32).min(*factor);
+            cluster.bounding_texture.downsample_factor =
+                DownsampleFactor::new(&scaled_factor(*factor, *weight, low).max(min_factor));
+        }
+    }
+
+    // Place the clusters in the given order on pages numbered from 0.
+    // The first new page takes the spare placer, and a reset placer is left in it for the next call.
+    fn place_clusters<P: TexturePlacer>(
+        order: &[(&ClusterID, &Cluster)],
+        mut pages: Vec<Page<P>>,
+        spare: &mut Option<P>,
+        config: &TexturePlacerConfig,
+        page_selection: &PageSelection,
+        dedicated: &HashSet<ClusterID>,
+    ) -> PlacedPages {
+        let mut dedicated_pages: HashMap<PolygonID, AtlasID> = HashMap::new();
+
+        let mut placed_uv_polygon_map: HashMap<PolygonID, PlacedUVPolygon> = HashMap::new();
+        for &(cluster_id, cluster) in order {
+            let (placed_texture, placed_uv_polygons) = if dedicated.contains(cluster_id) {
+                let current_atlas_id = pages.len();
+                let (width, height) = cluster.bounding_texture.get_scaled_size();
+                let size =
+                    config.fit_page_size(width + config.padding(), height + config.padding());
+                pages.push(Page {
+                    placer: None,
+                    atlas: Vec::new(),
+                    used_area: 0,
+                    size,
+                });
+                for (polygon_id, _) in &cluster.uv_polygons {
+                    dedicated_pages.insert(polygon_id.clone(), current_atlas_id);
+                }
+                place_on_dedicated_page(config, size, cluster, cluster_id, current_atlas_id)
+            } else {
+                let current_atlas_id =
+                    page_selection.select(&pages, cluster).unwrap_or_else(|| {
+                        let placer = match spare.take() {
+                            // The caller's placer may hold reserved areas that leave no room
+                            Some(placer)
+                                if !placer.can_place_cluster(
+                                    &cluster.bounding_texture,
+                                    &cluster.uv_polygons,
+                                ) =>
+                            {
+                                placer.new_page().unwrap_or_else(|| {
+                                    let mut placer = placer;
+                                    placer.reset_param();
+                                    placer
+                                })
+                            }
+                            Some(placer) => placer,
+                            None => next_page_placer(&mut pages),
+                        };
+                        pages.push(Page::new(placer));
+                        pages.len() - 1
+                    });
+                pages[current_atlas_id]
+                    .placer
+                    .as_mut()
+                    .unwrap()
+                    .place_texture(
+                        cluster.bounding_texture.clone(),
+                        cluster.uv_polygons.clone(),
+                        cluster_id.clone(),
+                        current_atlas_id,
+                    )
+            };
+
+            let page = &mut pages[placed_texture.atlas_id];
+            page.used_area += placed_texture.width as u64 * placed_texture.height as u64;
+            page.atlas.push(placed_texture);
+
+            let polygon_ids = cluster
+                .uv_polygons
+                .iter()
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>();
+
+            for (polygon_id, placed_uv_polygon) in polygon_ids.iter().zip(placed_uv_polygons) {
+                if let Some(placed_uv_polygon) = placed_uv_polygon {
+                    placed_uv_polygon_map.insert((*polygon_id).clone(), placed_uv_polygon.clone());
+                }
+            }
+        }
+
+        if spare.is_none() {
+            *spare = pages
+                .iter_mut()
+                .rev()
+                .find_map(|page| page.placer.take())
+                .map(|mut placer| {
+                    placer.reset_param();
+                    placer
+                });
+        }
+
+        PlacedPages {
+            pages: pages
+                .into_iter()
+                .map(|page| (page.atlas, page.size))
+                .collect(),
+            placed_uv_polygon_map,
+            dedicated_pages,
+        }
+    }
+
+    // Merge independently placed pages into one result with unique atlas IDs
+    fn assemble(
+        &self,
+        prepared: PreparedClusters,
+        placed: Vec<PlacedPages>,
+        config: &TexturePlacerConfig,
+    ) -> PackedAtlasProvider {
+        let PreparedClusters {
+            clusters,
+            mut oversized,
+            ..
+        } = prepared;
+        let mut atlases: HashMap<AtlasID, Atlas> = HashMap::new();
+        let mut page_sizes: HashMap<AtlasID, (u32, u32)> = HashMap::new();
+        let mut placed_uv_polygon_map: HashMap<PolygonID, PlacedUVPolygon> = HashMap::new();
+
+        for placed in placed {
+            let offset = atlases.len();
+            for (atlas_id, (mut atlas, size)) in placed.pages.into_iter().enumerate() {
+                for placed_texture in atlas.iter_mut() {
+                    placed_texture.atlas_id += offset;
+                }
+                atlases.insert(offset + atlas_id, atlas);
+                page_sizes.insert(offset + atlas_id, size);
+            }
+            for (polygon_id, mut placed_uv_polygon) in placed.placed_uv_polygon_map {
+                placed_uv_polygon.atlas_id += offset;
+                placed_uv_polygon_map.insert(polygon_id, placed_uv_polygon);
+            }
+            for (polygon_id, atlas_id) in placed.dedicated_pages {
+                oversized.insert(
+                    polygon_id,
+                    OversizeOutcome::DedicatedPage(offset + atlas_id),
+                );
+            }
+        }
+
+        let mut packed = PackedAtlasProvider {
+            clusters,
+            atlases,
+            page_sizes,
+            placed_uv_polygon_map,
+            oversized,
+            pages_shrunk: self.shrink_pages,
+        };
+        if self.shrink_pages {
+            packed.shrink_pages(config, 0);
+        }
+        packed
+    }
+}
+
+// Split sorted clusters by group key, keeping the order within each group
+fn split_by_group<'a>(
+    sorted: Vec<(&'a ClusterID, &'a Cluster)>,
+) -> Vec<Vec<(&'a ClusterID, &'a Cluster)>> {
+    let mut groups = BTreeMap::new();
+    for (cluster_id, cluster) in sorted {
+        groups
+            .entry(&cluster.group)
+            .or_insert_with(Vec::new)
+            .push((cluster_id, cluster));
+    }
+    groups.into_values().collect()
+}
+
+/// One packing strategy tried by `AtlasPacker::pack_best`
+#[derive(Clone)]
+pub struct PackingCandidate {
+    pub name: String,
+    pub placer: Box<dyn DynTexturePlacer>,
+    pub sort_strategy: ClusterSortStrategy,
+    pub page_selection: PageSelection,
+}
+
+impl PackingCandidate {
+    pub fn new(name: impl Into<String>, placer: impl DynTexturePlacer + 'static) -> Self {
+        Self {
+            name: name.into(),
+            placer: placer.into_box(),
+            sort_strategy: ClusterSortStrategy::default(),
+            page_selection: PageSelection::default(),
+        }
+    }
+
+    pub fn with_sort_strategy(mut self, sort_strategy: ClusterSortStrategy) -> Self {
+        self.sort_strategy = sort_strategy;
+        self
+    }
+
+    pub fn with_page_selection(mut self, page_selection: PageSelection) -> Self {
+        self.page_selection = page_selection;
+        self
+    }
+}
+
+/// Result of one `PackingCandidate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateScore {
+    pub name: String,
+    pub page_count: usize,
+    /// Placed texture area divided by the total page area
+    pub occupancy: f64,
+}
+
+// Clusters ready to be placed, after the oversized ones are handled
+struct PreparedClusters {
+    clusters: HashMap<ClusterID, Cluster>,
+    // Oversized clusters to put alone on a page
+    dedicated: HashSet<ClusterID>,
+    oversized: HashMap<PolygonID, OversizeOutcome>,
+}
+
+// Pages produced from a set of clusters, numbered from 0
+struct PlacedPages {
+    pages: Vec<(Atlas, (u32, u32))>,
+    placed_uv_polygon_map: HashMap<PolygonID, PlacedUVPolygon>,
+    dedicated_pages: HashMap<PolygonID, AtlasID>,
+}
+
+pub type PolygonKeyFn = Arc<dyn Fn(&PolygonID) -> u64 + Send + Sync>;
+
+/// How `AtlasPacker::pack_parallel` splits the clusters into independently packed groups
+#[derive(Clone)]
+pub enum ParallelGrouping {
+    /// One group per source image
+    SourceImage,
+    /// Consecutive clusters (in sort order) up to the given number of texels per group
+    AreaBudget(u64),
+    /// Clusters with the same key form a group; a cluster takes the key of its smallest polygon ID
+    SpatialKey(PolygonKeyFn),
+}
+
+impl ParallelGrouping {
+    fn group<'a>(
+        &self,
+        sorted: Vec<(&'a ClusterID, &'a Cluster)>,
+    ) -> Vec<Vec<(&'a ClusterID, &'a Cluster)>> {
+        match self {
+            ParallelGrouping::SourceImage => {
+                let mut groups = BTreeMap::new();
+                for (cluster_id, cluster) in sorted {
+                    groups
+                        .entry(cluster.bounding_texture.image_path.clone())
+                        .or_insert_with(Vec::new)
+                        .push((cluster_id, cluster));
+                }
+                groups.into_values().collect()
+            }
+            ParallelGrouping::AreaBudget(budget) => {
+                let mut groups: Vec<Vec<_>> = Vec::new();
+                let mut area = 0;
+                for (cluster_id, cluster) in sorted {
+                    let (width, height) = cluster.bounding_texture.get_scaled_size();
+                    let cluster_area = width as u64 * height as u64;
+                    if groups.is_empty() || area + cluster_area > *budget {
+                        groups.push(Vec::new());
+                        area = 0;
+                    }
+                    area += cluster_area;
+                    groups.last_mut().unwrap().push((cluster_id, cluster));
+                }
+                groups
+            }
+            ParallelGrouping::SpatialKey(key) => {
+                let mut groups = BTreeMap::new();
+                for (cluster_id, cluster) in sorted {
+                    groups
+                        .entry(cluster.min_polygon_id().map(|id| key(id)))
+                        .or_insert_with(Vec::new)
+                        .push((cluster_id, cluster));
+                }
+                groups.into_values().collect()
+            }
+        }
+    }
+}
+
+// Place a cluster alone at the top-left corner of a page sized to hold it
+fn place_on_dedicated_page(
+    config: &TexturePlacerConfig,
+    (page_width, page_height): (u32, u32),
+    cluster: &Cluster,
+    cluster_id: &ClusterID,
+    atlas_id: AtlasID,
+) -> (PlacedTextureGeometry, Vec<Option<PlacedUVPolygon>>) {
+    let (width, height) = cluster.bounding_texture.get_scaled_size();
+    let page_config = TexturePlacerConfig {
+        width: page_width,
+        height: page_height,
+        ..config.clone()
+    };
+    let placed = PlacedTextureGeometry {
+        cluster_id: cluster_id.clone(),
+        atlas_id,
+        origin: (config.padding(), config.padding()),
+        width,
+        height,
+        rotated: false,
+        mask: None,
+    };
+    let placed_uv_polygons = place_children(&page_config, &placed, &cluster.uv_polygons);
+    (placed, placed_uv_polygons)
+}
+
+pub struct PackedAtlasProvider {
+    pub(crate) atlases: HashMap<AtlasID, Atlas>,
+    pub(crate) clusters: HashMap<ClusterID, Cluster>,
+    // (width, height) of each page
+    pub(crate) page_sizes: HashMap<AtlasID, (u32, u32)>,
+    pub(crate) placed_uv_polygon_map: HashMap<PolygonID, PlacedUVPolygon>,
+    // Polygons of the clusters that did not fit an empty page
+    pub(crate) oversized: HashMap<PolygonID, OversizeOutcome>,
+    // Pages were shrunk to their used area, which `compact` does again
+    pub(crate) pages_shrunk: bool,
+}
 
 impl PackedAtlasProvider {
+    // Pages before `first_atlas_id` are left alone so that their UVs stay where they are
+    fn shrink_pages(&mut self, config: &TexturePlacerConfig, first_atlas_id: AtlasID) {
+        for (atlas_id, atlas) in self.atlases.iter() {
+            if *atlas_id < first_atlas_id {
+                continue;
+            }
+            let (used_width, used_height) = atlas.iter().fold((0, 0), |(width, height), placed| {
+                (
+                    width.max(placed.origin.0 + placed.width),
+                    height.max(placed.origin.1 + placed.height),
+                )
+            });
+            let (width, height) = self.page_sizes[atlas_id];
+            let (fit_width, fit_height) = config.fit_page_size(used_width, used_height);
+            let (new_width, new_height) = (fit_width.min(width), fit_height.min(height));
+            if (new_width, new_height) == (width, height) {
+                continue;
+            }
+
+            let scale_u = width as f64 / new_width as f64;
+            let scale_v = height as f64 / new_height as f64;
+            for placed in atlas {
+                let cluster = &self.clusters[&placed.cluster_id];
+                for (polygon_id, _) in &cluster.uv_polygons {
+                    if let Some(placed_uv_polygon) = self.placed_uv_polygon_map.get_mut(polygon_id)
+                    {
+                        for (u, v) in placed_uv_polygon.placed_uv_coords.iter_mut() {
+                            *u *= scale_u;
+                            *v = 1.0 - (1.0 - *v) * scale_v;
+                        }
+                    }
+                }
+            }
+            self.page_sizes.insert(*atlas_id, (new_width, new_height));
+        }
+    }
+
     pub fn export<E: AtlasExporter>(
         &self,
         exporter: E,
         output_dir: &Path,
         texture_cache: &TextureCache,
-        width: u32,
-        height: u32,
     ) {
         self.atlases.par_iter().for_each(|(id, atlas)| {
+            let (width, height) = self.page_sizes[id];
             let output_path = output_dir.join(id.to_string());
             exporter.export(
                 atlas,
@@ -230,4 +1817,1243 @@ impl PackedAtlasProvider {
     pub fn get_texture_info(&self, polygon_id: &PolygonID) -> Option<&PlacedUVPolygon> {
         self.placed_uv_polygon_map.get(polygon_id)
     }
+
+    pub fn get_page_size(&self, atlas_id: AtlasID) -> Option<(u32, u32)> {
+        self.page_sizes.get(&atlas_id).copied()
+    }
+
+    /// Downsample factor with which the polygon was placed, after every reduction
+    pub fn get_effective_downsample_factor(&self, polygon_id: &PolygonID) -> Option<f32> {
+        let placed = self.placed_uv_polygon_map.get(polygon_id)?;
+        let cluster = self.clusters.get(&placed.cluster_id)?;
+        Some(cluster.bounding_texture.downsample_factor.value())
+    }
+
+    pub fn effective_downsample_factors(&self) -> impl Iterator<Item = (&PolygonID, f32)> {
+        self.placed_uv_polygon_map.keys().filter_map(|polygon_id| {
+            Some((
+                polygon_id,
+                self.get_effective_downsample_factor(polygon_id)?,
+            ))
+        })
+    }
+
+    /// Remove the polygons from the pack result. Clusters left without polygons are dropped, and
+    /// the others are cropped to their remaining polygons where they are placed (clusters placed
+    /// with a mask keep their texture). Pages that become empty are dropped; the other pages keep
+    /// their IDs. Returns the remaining polygons whose UVs changed, in ascending order.
+    ///
+    /// Nothing moves, so the freed area stays where it is: `AtlasPacker::pack_into` can fill it,
+    /// and `compact` packs the remaining clusters again to close the holes.
+    pub fn remove_polygons(&mut self, polygon_ids: &[PolygonID]) -> Vec<PolygonID> {
+        let removed = polygon_ids.iter().collect::<HashSet<_>>();
+        let mut affected = Vec::new();
+        for (cluster_id, cluster) in self.clusters.iter_mut() {
+            let count = cluster.uv_polygons.len();
+            cluster
+                .uv_polygons
+                .retain(|(polygon_id, _)| !removed.contains(polygon_id));
+            if cluster.uv_polygons.len() != count {
+                affected.push(cluster_id.clone());
+            }
+        }
+        for polygon_id in polygon_ids {
+            self.placed_uv_polygon_map.remove(polygon_id);
+            self.oversized.remove(polygon_id);
+        }
+
+        let mut moved = Vec::new();
+        for cluster_id in affected {
+            if self.clusters[&cluster_id].uv_polygons.is_empty() {
+                self.clusters.remove(&cluster_id);
+                for atlas in self.atlases.values_mut() {
+                    atlas.retain(|placed| placed.cluster_id != cluster_id);
+                }
+                continue;
+            }
+
+            // Skipped clusters have no placement
+            let Some(placed) = self
+                .atlases
+                .values_mut()
+                .flatten()
+                .find(|placed| placed.cluster_id == cluster_id)
+            else {
+                continue;
+            };
+            if placed.mask.is_some() {
+                continue;
+            }
+            let cluster = self.clusters.get_mut(&cluster_id).unwrap();
+            let children = cluster
+                .uv_polygons
+                .iter()
+                .map(|(_, child)| child.clone())
+                .collect::<Vec<_>>();
+            let (bounding_texture, children) = cluster.bounding_texture.fit_children(&children);
+            for ((_, child), fitted) in cluster.uv_polygons.iter_mut().zip(children) {
+                *child = fitted;
+            }
+            let (width, height) = bounding_texture.get_scaled_size();
+            (placed.width, placed.height) = if placed.rotated {
+                (height, width)
+            } else {
+                (width, height)
+            };
+            cluster.bounding_texture = bounding_texture;
+
+            let (page_width, page_height) = self.page_sizes[&placed.atlas_id];
+            let config = TexturePlacerConfig {
+                width: page_width,
+                height: page_height,
+                ..Default::default()
+            };
+            for placed_uv_polygon in place_children(&config, placed, &cluster.uv_polygons)
+                .into_iter()
+                .flatten()
+            {
+                let polygon_id = placed_uv_polygon.polygon_id.clone();
+                let previous = self
+                    .placed_uv_polygon_map
+                    .insert(polygon_id.clone(), placed_uv_polygon);
+                if previous.is_some_and(|previous| {
+                    previous.placed_uv_coords
+                        != self.placed_uv_polygon_map[&polygon_id].placed_uv_coords
+                }) {
+                    moved.push(polygon_id);
+                }
+            }
+        }
+        self.atlases.retain(|_, atlas| !atlas.is_empty());
+        self.page_sizes
+            .retain(|atlas_id, _| self.atlases.contains_key(atlas_id));
+        moved.sort();
+        moved
+    }
+
+    /// Place all placed clusters again on new pages, which closes the holes left by removed
+    /// polygons. Clusters that had a dedicated page get a new one, and the pages are shrunk
+    /// again if they were shrunk when packed.
+    /// Returns the polygons whose UVs or pages changed, in ascending order.
+    pub fn compact<P: TexturePlacer>(
+        &mut self,
+        mut placer: P,
+        sort_strategy: &ClusterSortStrategy,
+    ) -> Vec<PolygonID> {
+        placer.reset_param();
+        let config = placer.config().clone();
+
+        let dedicated = self
+            .clusters
+            .iter()
+            .filter(|(_, cluster)| {
+                cluster.uv_polygons.iter().any(|(polygon_id, _)| {
+                    matches!(
+                        self.oversized.get(polygon_id),
+                        Some(OversizeOutcome::DedicatedPage(_))
+                    )
+                })
+            })
+            .map(|(cluster_id, _)| cluster_id.clone())
+            .collect::<HashSet<_>>();
+        let placed = {
+            let placed_clusters = self
+                .atlases
+                .values()
+                .flatten()
+                .map(|placed| &placed.cluster_id)
+                .collect::<HashSet<_>>();
+            let order = sort_strategy.sort(
+                self.clusters
+                    .iter()
+                    .filter(|(cluster_id, _)| placed_clusters.contains(cluster_id)),
+            );
+            let mut spare = Some(placer);
+            split_by_group(order)
+                .into_iter()
+                .map(|group| {
+                    AtlasPacker::place_clusters(
+                        &group,
+                        Vec::new(),
+                        &mut spare,
+                        &config,
+                        &PageSelection::FirstFit,
+                        &dedicated,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        self.atlases.clear();
+        self.page_sizes.clear();
+        let previous = std::mem::take(&mut self.placed_uv_polygon_map);
+        for placed in placed {
+            let offset = self.atlases.len();
+            for (atlas_id, (mut atlas, size)) in placed.pages.into_iter().enumerate() {
+                for placed_texture in atlas.iter_mut() {
+                    placed_texture.atlas_id += offset;
+                }
+                self.atlases.insert(offset + atlas_id, atlas);
+                self.page_sizes.insert(offset + atlas_id, size);
+            }
+            for (polygon_id, mut placed_uv_polygon) in placed.placed_uv_polygon_map {
+                placed_uv_polygon.atlas_id += offset;
+                self.placed_uv_polygon_map
+                    .insert(polygon_id, placed_uv_polygon);
+            }
+            for (polygon_id, atlas_id) in placed.dedicated_pages {
+                self.oversized.insert(
+                    polygon_id,
+                    OversizeOutcome::DedicatedPage(offset + atlas_id),
+                );
+            }
+        }
+        if self.pages_shrunk {
+            self.shrink_pages(&config, 0);
+        }
+        let mut moved = self
+            .placed_uv_polygon_map
+            .iter()
+            .filter(|(polygon_id, placed_uv_polygon)| {
+                previous.get(*polygon_id).is_none_or(|previous| {
+                    previous.atlas_id != placed_uv_polygon.atlas_id
+                        || previous.placed_uv_coords != placed_uv_polygon.placed_uv_coords
+                })
+            })
+            .map(|(polygon_id, _)| polygon_id.clone())
+            .collect::<Vec<_>>();
+        moved.sort();
+        moved
+    }
+
+    /// Group of the clusters on the page (None for pages of ungrouped or no clusters)
+    pub fn get_page_group(&self, atlas_id: AtlasID) -> Option<&GroupKey> {
+        let placed = self.atlases.get(&atlas_id)?.first()?;
+        self.clusters.get(&placed.cluster_id)?.group.as_ref()
+    }
+
+    /// Page IDs of every group, in ascending order
+    pub fn group_pages(&self) -> BTreeMap<GroupKey, Vec<AtlasID>> {
+        let mut group_pages = BTreeMap::new();
+        for atlas_id in self.atlases.keys() {
+            if let Some(group) = self.get_page_group(*atlas_id) {
+                group_pages
+                    .entry(group.clone())
+                    .or_insert_with(Vec::new)
+                    .push(*atlas_id);
+            }
+        }
+        for atlas_ids in group_pages.values_mut() {
+            atlas_ids.sort();
+        }
+        group_pages
+    }
+
+    /// Placed texture area divided by the total page area
+    pub fn occupancy(&self) -> f64 {
+        let used = self
+            .atlases
+            .values()
+            .flatten()
+            .map(|placed| placed.width as u64 * placed.height as u64)
+            .sum::<u64>();
+        let total = self
+            .page_sizes
+            .values()
+            .map(|(width, height)| *width as u64 * *height as u64)
+            .sum::<u64>();
+        if total == 0 {
+            return 0.0;
+        }
+        used as f64 / total as f64
+    }
+
+    pub fn get_oversize_outcome(&self, polygon_id: &PolygonID) -> Option<&OversizeOutcome> {
+        self.oversized.get(polygon_id)
+    }
+
+    pub fn oversized_polygons(&self) -> impl Iterator<Item = (&PolygonID, &OversizeOutcome)> {
+        self.oversized.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::texture::DownsampleFactor;
+
+    // A polygon of `size` x `size` pixels at `offset` in a 1024x1024 image
+    fn square_polygon(image: &str, offset: u32, size: u32) -> PolygonMappedTexture {
+        let (min, max) = (offset as f64 / 1024.0, (offset + size) as f64 / 1024.0);
+        PolygonMappedTexture::new(
+            &PathBuf::from(image),
+            (1024, 1024),
+            &[(min, 1.0 - min), (max, 1.0 - min), (max, 1.0 - max)],
+            DownsampleFactor::new(&1.0),
+        )
+    }
+
+    #[test]
+    fn test_sort_strategy() {
+        let mut packer = AtlasPacker::default();
+        for (i, size) in [30, 10, 50, 20, 40].into_iter().enumerate() {
+            packer.add_texture(i.to_string(), square_polygon("a.png", i as u32 * 100, size));
+        }
+        let clusters = packer.create_clusters();
+
+        let heights = |strategy: ClusterSortStrategy| {
+            strategy
+                .sort(&clusters)
+                .iter()
+                .map(|(_, cluster)| cluster.bounding_texture.get_scaled_size().1)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            heights(ClusterSortStrategy::Height(SortOrder::Descending)),
+            vec![54, 44, 34, 24, 14]
+        );
+        assert_eq!(
+            heights(ClusterSortStrategy::Area(SortOrder::Ascending)),
+            vec![14, 24, 34, 44, 54]
+        );
+        assert_eq!(
+            heights(ClusterSortStrategy::Custom(Arc::new(|a, b| {
+                b.get_scaled_size().0.cmp(&a.get_scaled_size().0)
+            }))),
+            vec![54, 44, 34, 24, 14]
+        );
+    }
+
+    #[test]
+    fn test_sort_strategy_is_deterministic() {
+        // Each packer has its own hash map state, so the cluster iteration order differs
+        let order = || {
+            let mut packer = AtlasPacker::default();
+            for i in 0..20 {
+                let image = format!("{}.png", i % 3);
+                packer.add_texture(format!("polygon_{}", i), square_polygon(&image, i * 40, 16));
+            }
+            let clusters = packer.create_clusters();
+            ClusterSortStrategy::SourceImage(SortOrder::Ascending)
+                .sort(&clusters)
+                .iter()
+                .map(|(_, cluster)| cluster.min_polygon_id().cloned().unwrap())
+                .collect::<Vec<_>>()
+        };
+        let expected = order();
+        for _ in 0..5 {
+            assert_eq!(order(), expected);
+        }
+        assert!(expected.starts_with(&["polygon_0".to_string(), "polygon_12".to_string()]));
+    }
+
+    #[test]
+    fn test_page_selection() {
+        use crate::place::{GuillotineTexturePlacer, TexturePlacerConfig};
+
+        // Neither `Clone` nor `new_page`, so it fills one page at a time
+        struct SinglePagePlacer(GuillotineTexturePlacer);
+
+        impl TexturePlacer for SinglePagePlacer {
+            fn config(&self) -> &TexturePlacerConfig {
+                self.0.config()
+            }
+
+            fn place_texture(
+                &mut self,
+                bounding_texture: ClusterBoundingTexture,
+                children: Vec<(PolygonID, ChildUVPolygon)>,
+                cluster_id: ClusterID,
+                parent_atlas_id: AtlasID,
+            ) -> (PlacedTextureGeometry, Vec<Option<PlacedUVPolygon>>) {
+                self.0
+                    .place_texture(bounding_texture, children, cluster_id, parent_atlas_id)
+            }
+
+            fn can_place(&self, texture: &ClusterBoundingTexture) -> bool {
+                self.0.can_place(texture)
+            }
+
+            fn reset_param(&mut self) {
+                self.0.reset_param()
+            }
+        }
+
+        // Two 44px clusters need a page each, and the holes next to each one hold eight 12px clusters
+        fn page_count<P: TexturePlacer>(page_selection: PageSelection, placer: P) -> usize {
+            let mut packer = AtlasPacker::default()
+                .with_sort_strategy(ClusterSortStrategy::Height(SortOrder::Descending))
+                .with_page_selection(page_selection);
+            for i in 0..2 {
+                let image = format!("{}.png", i);
+                packer.add_texture(format!("large_{}", i), square_polygon(&image, 0, 40));
+            }
+            for i in 0..12 {
+                packer.add_texture(
+                    format!("small_{}", i),
+                    square_polygon("small.png", i * 20, 8),
+                );
+            }
+            let packed = packer.pack(placer);
+            assert_eq!(packed.placed_uv_polygon_map.len(), 14);
+            packed.atlases.len()
+        }
+        let placer = || GuillotineTexturePlacer::new(TexturePlacerConfig::new(64, 64, 0));
+        assert_eq!(page_count(PageSelection::Single, placer()), 3);
+        assert_eq!(page_count(PageSelection::FirstFit, placer()), 2);
+        assert_eq!(page_count(PageSelection::BestFit, placer()), 2);
+        assert_eq!(
+            page_count(PageSelection::FirstFit, SinglePagePlacer(placer())),
+            3
+        );
+    }
+
+    #[test]
+    fn test_pack_with_full_placer() {
+        use crate::place::{GuillotineTexturePlacer, TexturePlacerConfig};
+
+        // The reserved area leaves no room for any cluster on the caller's page
+        let mut placer = GuillotineTexturePlacer::new(TexturePlacerConfig::new(64, 64, 0));
+        assert!(placer.reserve(&PlacedTextureGeometry::new(
+            "reserved".to_string(),
+            0,
+            (0, 0),
+            60,
+            60
+        )));
+
+        let mut packer = AtlasPacker::default();
+        for i in 0..3 {
+            packer.add_texture(
+                format!("polygon_{}", i),
+                square_polygon(&format!("{}.png", i), 0, 20),
+            );
+        }
+        let packed = packer.pack(placer);
+        assert_eq!(packed.placed_uv_polygon_map.len(), 3);
+        assert_eq!(packed.atlases.len(), 1);
+    }
+
+    #[test]
+    fn test_page_shrinking() {
+        use crate::place::GuillotineTexturePlacer;
+
+        // 32px and 16px clusters side by side on a 128x128 page
+        let mut packer = AtlasPacker::default()
+            .with_sort_strategy(ClusterSortStrategy::Height(SortOrder::Descending))
+            .with_page_shrinking(true);
+        packer.add_texture("large".to_string(), square_polygon("0.png", 0, 28));
+        packer.add_texture("small".to_string(), square_polygon("1.png", 0, 12));
+        let config = TexturePlacerConfig::new(128, 128, 0);
+        let packed = packer.pack(GuillotineTexturePlacer::new(config));
+        assert_eq!(packed.get_page_size(0), Some((64, 32)));
+
+        // The UVs of every polygon must still point at its placed texture
+        for placed_uv_polygon in packed.placed_uv_polygon_map.values() {
+            let placed = packed.atlases[&0]
+                .iter()
+                .find(|placed| placed.cluster_id == placed_uv_polygon.cluster_id)
+                .unwrap();
+            let (x, y) = placed_uv_polygon.placed_uv_coords[0];
+            let (x, y) = (x * 64.0, (1.0 - y) * 32.0);
+            assert_eq!(
+                (x as u32, y as u32),
+                (placed.origin.0 + 2, placed.origin.1 + 2)
+            );
+        }
+    }
+
+    #[test]
+    fn test_oversize_policy() {
+        use crate::place::GuillotineTexturePlacer;
+
+        // A 100px cluster on 64x64 pages, next to a small one
+        let pack = |oversize_policy| {
+            let mut packer = AtlasPacker::default()
+                .with_sort_strategy(ClusterSortStrategy::Height(SortOrder::Descending))
+                .with_oversize_policy(oversize_policy);
+            packer.add_texture("large".to_string(), square_polygon("0.png", 0, 96));
+            packer.add_texture("small".to_string(), square_polygon("1.png", 0, 12));
+            let config = TexturePlacerConfig::new(64, 64, 0);
+            packer.pack(GuillotineTexturePlacer::new(config))
+        };
+        let large = "large".to_string();
+        let small = "small".to_string();
+
+        let packed = pack(OversizePolicy::Downscale);
+        assert!(packed.get_texture_info(&large).is_some());
+        let Some(OversizeOutcome::Downscaled(factor)) = packed.get_oversize_outcome(&large) else {
+            panic!("the large cluster must be downscaled");
+        };
+        assert!(100.0 * factor <= 64.0);
+        assert_eq!(packed.get_oversize_outcome(&small), None);
+
+        let packed = pack(OversizePolicy::DedicatedPage);
+        assert_eq!(packed.atlases.len(), 2);
+        assert_eq!(
+            packed.get_oversize_outcome(&large),
+            Some(&OversizeOutcome::DedicatedPage(0))
+        );
+        assert_eq!(packed.get_page_size(0), Some((128, 128)));
+        assert_eq!(packed.get_page_size(1), Some((64, 64)));
+        assert_eq!(packed.get_texture_info(&small).unwrap().atlas_id, 1);
+
+        let packed = pack(OversizePolicy::Skip);
+        assert_eq!(packed.atlases.len(), 1);
+        assert!(packed.get_texture_info(&large).is_none());
+        assert!(packed.get_texture_info(&small).is_some());
+        assert_eq!(
+            packed.oversized_polygons().collect::<Vec<_>>(),
+            vec![(&large, &OversizeOutcome::Skipped)]
+        );
+
+        // No factor helps when the padding takes the whole page, so the cluster is skipped
+        let mut packer = AtlasPacker::default();
+        packer.add_texture("small".to_string(), square_polygon("1.png", 0, 12));
+        let config = TexturePlacerConfig::new(64, 64, 64);
+        let packed = packer.pack(GuillotineTexturePlacer::new(config));
+        assert!(packed.atlases.is_empty());
+        assert_eq!(
+            packed.get_oversize_outcome(&small),
+            Some(&OversizeOutcome::Skipped)
+        );
+    }
+
+    #[test]
+    fn test_oversize_split() {
+        use crate::place::GuillotineTexturePlacer;
+
+        // A chain of overlapping 20px polygons spanning 140px becomes a single cluster
+        let mut packer = AtlasPacker::default().with_oversize_policy(OversizePolicy::Split);
+        for i in 0..8 {
+            packer.add_texture(
+                format!("polygon_{}", i),
+                square_polygon("0.png", i * 16, 20),
+            );
+        }
+        assert_eq!(packer.create_clusters().len(), 1);
+
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let packed = packer.pack(GuillotineTexturePlacer::new(config));
+        assert!(packed.clusters.len() > 1);
+        for cluster in packed.clusters.values() {
+            let (width, height) = cluster.bounding_texture.get_scaled_size();
+            assert!(width <= 64 && height <= 64);
+            assert_eq!(cluster.bounding_texture.downsample_factor.value(), 1.0);
+        }
+        for i in 0..8 {
+            let polygon_id = format!("polygon_{}", i);
+            assert!(packed.get_texture_info(&polygon_id).is_some());
+            assert_eq!(
+                packed.get_oversize_outcome(&polygon_id),
+                Some(&OversizeOutcome::Split)
+            );
+        }
+        let polygon_count = packed
+            .clusters
+            .values()
+            .map(|cluster| cluster.uv_polygons.len())
+            .sum::<usize>();
+        assert_eq!(polygon_count, 8);
+    }
+
+    #[test]
+    fn test_pack_parallel() {
+        use crate::place::GuillotineTexturePlacer;
+
+        let new_packer = || {
+            let mut packer = AtlasPacker::default()
+                .with_sort_strategy(ClusterSortStrategy::Height(SortOrder::Descending));
+            for i in 0..40 {
+                let image = format!("{}.png", i % 4);
+                packer.add_texture(format!("polygon_{}", i), square_polygon(&image, i * 20, 12));
+            }
+            packer
+        };
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let groupings = [
+            ParallelGrouping::SourceImage,
+            ParallelGrouping::AreaBudget(64 * 64),
+            ParallelGrouping::SpatialKey(Arc::new(|polygon_id: &PolygonID| {
+                (polygon_id.len() % 2) as u64
+            })),
+        ];
+        for grouping in groupings {
+            let packed =
+                new_packer().pack_parallel(GuillotineTexturePlacer::new(config.clone()), grouping);
+            assert_eq!(packed.placed_uv_polygon_map.len(), 40);
+
+            // Atlas IDs are unique and every placement refers to its own page
+            let mut atlas_ids = packed.atlases.keys().copied().collect::<Vec<_>>();
+            atlas_ids.sort();
+            assert_eq!(atlas_ids, (0..packed.atlases.len()).collect::<Vec<_>>());
+            for (atlas_id, atlas) in packed.atlases.iter() {
+                assert!(!atlas.is_empty());
+                for placed in atlas {
+                    assert_eq!(placed.atlas_id, *atlas_id);
+                }
+            }
+            for placed_uv_polygon in packed.placed_uv_polygon_map.values() {
+                assert!(packed.atlases[&placed_uv_polygon.atlas_id]
+                    .iter()
+                    .any(|placed| placed.cluster_id == placed_uv_polygon.cluster_id));
+            }
+        }
+
+        // Pages never mix source images
+        let packed = new_packer().pack_parallel(
+            GuillotineTexturePlacer::new(config),
+            ParallelGrouping::SourceImage,
+        );
+        for atlas in packed.atlases.values() {
+            let image_path = &packed.clusters[&atlas[0].cluster_id]
+                .bounding_texture
+                .image_path;
+            assert!(atlas.iter().all(|placed| {
+                &packed.clusters[&placed.cluster_id]
+                    .bounding_texture
+                    .image_path
+                    == image_path
+            }));
+        }
+    }
+
+    #[test]
+    fn test_pack_best() {
+        use crate::place::{
+            GuillotineTexturePlacer, MaxRectsHeuristic, MaxRectsTexturePlacer, SizeRounding,
+        };
+
+        // Same input as test_page_selection: only keeping earlier pages open fits it on 2 pages
+        let mut packer = AtlasPacker::default();
+        for i in 0..2 {
+            let image = format!("{}.png", i);
+            packer.add_texture(format!("large_{}", i), square_polygon(&image, 0, 40));
+        }
+        for i in 0..12 {
+            packer.add_texture(
+                format!("small_{}", i),
+                square_polygon("small.png", i * 20, 8),
+            );
+        }
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let by_height = ClusterSortStrategy::Height(SortOrder::Descending);
+        let candidates = vec![
+            PackingCandidate::new("guillotine", GuillotineTexturePlacer::new(config.clone()))
+                .with_sort_strategy(by_height.clone()),
+            PackingCandidate::new(
+                "max_rects",
+                MaxRectsTexturePlacer::new(config.clone(), MaxRectsHeuristic::default()),
+            )
+            .with_sort_strategy(by_height.clone())
+            .with_page_selection(PageSelection::FirstFit),
+            // Also 2 pages, but larger ones
+            PackingCandidate::new(
+                "max_rects_large",
+                MaxRectsTexturePlacer::new(
+                    TexturePlacerConfig::new_with_rounding(72, 72, 0, SizeRounding::Exact),
+                    MaxRectsHeuristic::default(),
+                ),
+            )
+            .with_sort_strategy(by_height.clone())
+            .with_page_selection(PageSelection::FirstFit),
+            PackingCandidate::new(
+                "guillotine_rotated",
+                GuillotineTexturePlacer::new(config.with_rotation(true)),
+            )
+            .with_sort_strategy(by_height)
+            .with_page_selection(PageSelection::FirstFit),
+        ];
+        let (packed, scores) = packer.pack_best(candidates);
+
+        let names = scores.iter().map(|s| s.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "guillotine",
+                "max_rects",
+                "max_rects_large",
+                "guillotine_rotated"
+            ]
+        );
+        assert_eq!(scores[0].page_count, 3);
+        assert_eq!(scores[1].page_count, 2);
+        assert_eq!(scores[2].page_count, 2);
+        assert_eq!(scores[3].page_count, 2);
+        assert!(scores[1].occupancy > scores[0].occupancy);
+        // On the same page count, the larger pages are less occupied
+        assert!(scores[1].occupancy > scores[2].occupancy);
+        assert_eq!(scores[1].occupancy, scores[3].occupancy);
+
+        // The earlier of the equally good candidates wins
+        assert_eq!(packed.atlases.len(), 2);
+        assert_eq!(packed.occupancy(), scores[1].occupancy);
+        assert!(packed.occupancy() > scores[2].occupancy);
+        assert_eq!(packed.placed_uv_polygon_map.len(), 14);
+    }
+
+    #[test]
+    fn test_pack_to_page_count() {
+        use crate::place::GuillotineTexturePlacer;
+
+        let new_packer = || {
+            let mut packer = AtlasPacker::default();
+            for i in 0..8 {
+                packer.add_texture(i.to_string(), square_polygon(&format!("{}.png", i), 0, 40));
+            }
+            packer
+        };
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let placer = GuillotineTexturePlacer::new(config);
+
+        // Every 44px cluster needs its own page at full size
+        let (packed, scale) = new_packer().pack_to_page_count(placer.clone(), 8).unwrap();
+        assert_eq!(scale, 1.0);
+        assert_eq!(packed.atlases.len(), 8);
+
+        // Four clusters per page once they are at most 32px
+        let (packed, scale) = new_packer().pack_to_page_count(placer.clone(), 2).unwrap();
+        assert!(scale > 0.6 && scale < 0.75, "scale: {}", scale);
+        assert!(packed.atlases.len() <= 2);
+        assert_eq!(packed.placed_uv_polygon_map.len(), 8);
+        for cluster in packed.clusters.values() {
+            assert_eq!(cluster.bounding_texture.downsample_factor.value(), scale);
+        }
+
+        assert!(new_packer().pack_to_page_count(placer, 0).is_none());
+    }
+
+    #[test]
+    fn test_texel_budget() {
+        use crate::place::GuillotineTexturePlacer;
+
+        let new_packer = |reduction| {
+            // Two 44px clusters with the buffer, 3872 texels in total
+            let mut packer = AtlasPacker::default().with_texel_budget(2000, reduction);
+            packer.add_texture("low".to_string(), square_polygon("a.png", 0, 40));
+            packer.add_texture(
+                "high".to_string(),
+                square_polygon("b.png", 0, 40).with_priority(4.0),
+            );
+            packer
+        };
+        let config = TexturePlacerConfig::new(128, 128, 0);
+        let total_area = |packed: &PackedAtlasProvider| {
+            packed
+                .atlases
+                .values()
+                .flatten()
+                .map(|placed| placed.width as u64 * placed.height as u64)
+                .sum::<u64>()
+        };
+
+        let packed = new_packer(BudgetReduction::Proportional)
+            .pack(GuillotineTexturePlacer::new(config.clone()));
+        assert!(total_area(&packed) <= 2000);
+        let low = packed
+            .get_effective_downsample_factor(&"low".to_string())
+            .unwrap();
+        let high = packed
+            .get_effective_downsample_factor(&"high".to_string())
+            .unwrap();
+        assert_eq!(low, high);
+        assert!(low > 0.65 && low < 0.75, "factor: {}", low);
+
+        // Four times the priority keeps four times the area
+        let packed = new_packer(BudgetReduction::Priority)
+            .pack(GuillotineTexturePlacer::new(config.clone()));
+        assert!(total_area(&packed) <= 2000);
+        let factors = packed
+            .effective_downsample_factors()
+            .collect::<HashMap<_, _>>();
+        let (low, high) = (factors[&"low".to_string()], factors[&"high".to_string()]);
+        assert!((high / low - 2.0).abs() < 0.1, "factors: {} {}", low, high);
+
+        // Within the budget nothing changes
+        let packed = new_packer(BudgetReduction::Proportional)
+            .with_texel_budget(4000, BudgetReduction::Proportional)
+            .pack(GuillotineTexturePlacer::new(config.clone()));
+        assert!(packed
+            .effective_downsample_factors()
+            .all(|(_, factor)| factor == 1.0));
+
+        // A budget below one texel per cluster keeps a 1px texture for each of them, and the
+        // cropped texture has the size of its placement
+        let mut packer = AtlasPacker::default().with_texel_budget(1, BudgetReduction::Proportional);
+        for i in 0..4 {
+            packer.add_texture(
+                format!("polygon_{}", i),
+                square_polygon(&format!("{}.png", i), 0, 40),
+            );
+        }
+        let packed = packer.pack(GuillotineTexturePlacer::new(config));
+        assert_eq!(packed.placed_uv_polygon_map.len(), 4);
+        assert!(packed
+            .effective_downsample_factors()
+            .all(|(_, factor)| factor > 0.0));
+        let image = image::DynamicImage::new_rgba8(64, 64);
+        for placed in packed.atlases.values().flatten() {
+            let texture = &packed.clusters[&placed.cluster_id].bounding_texture;
+            assert_eq!((placed.width, placed.height), (1, 1));
+            let cropped = texture.crop(&image);
+            assert_eq!((cropped.width(), cropped.height()), (1, 1));
+        }
+    }
+
+    #[test]
+    fn test_pack_into() {
+        use crate::place::{GuillotineTexturePlacer, ShelfHeuristic, ShelfTexturePlacer};
+
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let placer = GuillotineTexturePlacer::new(config.clone());
+
+        let mut packer = AtlasPacker::default();
+        packer.add_texture("large_0".to_string(), square_polygon("a.png", 0, 40));
+        let mut packed = packer.pack(placer.clone());
+        let before = packed.placed_uv_polygon_map.clone();
+
+        // Small textures fill the holes of the existing page
+        let mut packer = AtlasPacker::default();
+        for i in 0..3 {
+            packer.add_texture(format!("small_{}", i), square_polygon("b.png", i * 20, 8));
+        }
+        assert_eq!(packer.pack_into(&mut packed, placer.clone()), Ok(vec![0]));
+        assert_eq!(packed.atlases.len(), 1);
+        assert_eq!(packed.clusters.len(), 4);
+        assert_eq!(packed.placed_uv_polygon_map.len(), 4);
+
+        // A large texture needs a new page
+        let mut packer = AtlasPacker::default();
+        packer.add_texture("large_1".to_string(), square_polygon("c.png", 0, 40));
+        assert_eq!(packer.pack_into(&mut packed, placer.clone()), Ok(vec![1]));
+        assert_eq!(packed.atlases.len(), 2);
+        assert_eq!(packed.get_page_size(1), Some((64, 64)));
+
+        // Nothing moved, and the placements on the first page do not overlap
+        for (polygon_id, placed_uv_polygon) in before {
+            assert_eq!(
+                packed.placed_uv_polygon_map[&polygon_id].placed_uv_coords,
+                placed_uv_polygon.placed_uv_coords
+            );
+        }
+        let atlas = &packed.atlases[&0];
+        assert_eq!(atlas.len(), 4);
+        for (i, a) in atlas.iter().enumerate() {
+            for b in &atlas[i + 1..] {
+                assert!(
+                    a.origin.0 + a.width <= b.origin.0
+                        || b.origin.0 + b.width <= a.origin.0
+                        || a.origin.1 + a.height <= b.origin.1
+                        || b.origin.1 + b.height <= a.origin.1
+                );
+            }
+        }
+        for placed_uv_polygon in packed.placed_uv_polygon_map.values() {
+            assert!(packed.clusters.contains_key(&placed_uv_polygon.cluster_id));
+        }
+
+        // Polygons that are already packed are rejected, and nothing changes
+        let mut packer = AtlasPacker::default();
+        packer.add_texture("small_0".to_string(), square_polygon("b.png", 0, 8));
+        packer.add_texture("small_9".to_string(), square_polygon("b.png", 180, 8));
+        assert_eq!(
+            packer.pack_into(&mut packed, placer.clone()),
+            Err(PackIntoError::AlreadyPacked(vec!["small_0".to_string()]))
+        );
+        assert_eq!(packed.placed_uv_polygon_map.len(), 5);
+        assert_eq!(packed.clusters.len(), 5);
+
+        // The shelf placer cannot reserve the existing placements, so it is not silently
+        // replaced by another placer
+        let mut packer = AtlasPacker::default();
+        packer.add_texture("small_9".to_string(), square_polygon("b.png", 180, 8));
+        assert_eq!(
+            packer.pack_into(
+                &mut packed,
+                ShelfTexturePlacer::new(config.clone(), ShelfHeuristic::NextFit)
+            ),
+            Err(PackIntoError::ReserveUnsupported)
+        );
+        assert_eq!(packed.placed_uv_polygon_map.len(), 5);
+
+        // New pages follow the highest page ID, which need not be the page count
+        let first_page = ["large_0", "small_0", "small_1", "small_2"].map(String::from);
+        packed.remove_polygons(&first_page);
+        packed.atlases.remove(&0);
+        packed.page_sizes.remove(&0);
+        let mut packer = AtlasPacker::default();
+        packer.add_texture("large_2".to_string(), square_polygon("d.png", 0, 40));
+        assert_eq!(packer.pack_into(&mut packed, placer), Ok(vec![2]));
+        assert_eq!(packed.atlases.len(), 2);
+    }
+
+    #[test]
+    fn test_pack_into_dedicated_page() {
+        use crate::place::{MaxRectsHeuristic, MaxRectsTexturePlacer};
+
+        // The dedicated page has room left, but only the other page is filled
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let placer = MaxRectsTexturePlacer::new(config, MaxRectsHeuristic::default());
+        let mut packer = AtlasPacker::default().with_oversize_policy(OversizePolicy::DedicatedPage);
+        packer.add_texture("huge".to_string(), square_polygon("a.png", 0, 70));
+        packer.add_texture("small_0".to_string(), square_polygon("b.png", 0, 8));
+        let mut packed = packer.pack(placer.clone());
+        let dedicated = match packed.oversized["huge"] {
+            OversizeOutcome::DedicatedPage(atlas_id) => atlas_id,
+            ref outcome => panic!("unexpected outcome: {:?}", outcome),
+        };
+
+        let mut packer = AtlasPacker::default();
+        for i in 1..4 {
+            packer.add_texture(format!("small_{}", i), square_polygon("b.png", i * 20, 8));
+        }
+        let changed = packer.pack_into(&mut packed, placer).unwrap();
+        assert_eq!(changed, vec![1 - dedicated]);
+        assert_eq!(packed.atlases[&dedicated].len(), 1);
+    }
+
+    #[test]
+    fn test_remove_polygons() {
+        use crate::place::GuillotineTexturePlacer;
+
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let mut packer = AtlasPacker::default();
+        // "a" and "b" overlap and form one 34px cluster; "c" needs a second page
+        packer.add_texture("a".to_string(), square_polygon("a.png", 0, 20));
+        packer.add_texture("b".to_string(), square_polygon("a.png", 10, 20));
+        packer.add_texture("c".to_string(), square_polygon("c.png", 0, 40));
+        let mut packed = packer.pack(GuillotineTexturePlacer::new(config.clone()));
+        assert_eq!(packed.atlases.len(), 2);
+
+        // The cluster of "c" is dropped with its page, and the other page keeps its ID
+        let c_page = packed.placed_uv_polygon_map[&"c".to_string()].atlas_id;
+        assert!(packed.remove_polygons(&["c".to_string()]).is_empty());
+        assert_eq!(packed.clusters.len(), 1);
+        assert_eq!(
+            packed.atlases.keys().copied().collect::<Vec<_>>(),
+            [1 - c_page]
+        );
+        assert_eq!(packed.get_page_size(c_page), None);
+
+        // The cluster of "b" is cropped to "b", which moves its texels
+        assert_eq!(
+            packed.remove_polygons(&["a".to_string()]),
+            vec!["b".to_string()]
+        );
+        assert!(packed.get_texture_info(&"a".to_string()).is_none());
+        let placed = packed.atlases.values().flatten().next().unwrap().clone();
+        assert_eq!((placed.width, placed.height), (24, 24));
+        let b = packed.placed_uv_polygon_map[&"b".to_string()].clone();
+        for (u, v) in &b.placed_uv_coords {
+            let x = (u * 64.0).round() as u32 - placed.origin.0;
+            let y = ((1.0 - v) * 64.0).round() as u32 - placed.origin.1;
+            assert!([2, 22].contains(&x) && [2, 22].contains(&y), "{} {}", x, y);
+        }
+
+        // Compaction numbers the pages from 0 again
+        packed.compact(
+            GuillotineTexturePlacer::new(config.clone()),
+            &ClusterSortStrategy::Height(SortOrder::Descending),
+        );
+        assert_eq!(packed.atlases.len(), 1);
+        assert_eq!(packed.get_page_size(0), Some((64, 64)));
+        assert_eq!(packed.placed_uv_polygon_map.len(), 1);
+        assert_eq!(packed.placed_uv_polygon_map[&"b".to_string()].atlas_id, 0);
+
+        // Pages that were shrunk are shrunk again
+        let mut packer = AtlasPacker::default().with_page_shrinking(true);
+        packer.add_texture("a".to_string(), square_polygon("a.png", 0, 20));
+        packer.add_texture("b".to_string(), square_polygon("a.png", 10, 20));
+        let mut packed = packer.pack(GuillotineTexturePlacer::new(config.clone()));
+        assert_eq!(packed.get_page_size(0), Some((64, 64)));
+        packed.remove_polygons(&["a".to_string()]);
+        packed.compact(
+            GuillotineTexturePlacer::new(config),
+            &ClusterSortStrategy::default(),
+        );
+        assert_eq!(packed.get_page_size(0), Some((32, 32)));
+    }
+
+    #[test]
+    fn test_groups() {
+        use crate::place::GuillotineTexturePlacer;
+
+        let new_packer = || {
+            let mut packer = AtlasPacker::default()
+                .with_sort_strategy(ClusterSortStrategy::Height(SortOrder::Descending))
+                .with_page_selection(PageSelection::FirstFit);
+            // "a" and "b" overlap but are in different groups
+            packer.add_texture_with_group(
+                "a".to_string(),
+                square_polygon("a.png", 0, 20),
+                "lod1".to_string(),
+            );
+            packer.add_texture_with_group(
+                "b".to_string(),
+                square_polygon("a.png", 10, 20),
+                "lod2".to_string(),
+            );
+            for i in 0..3 {
+                packer.add_texture_with_group(
+                    format!("lod1_{}", i),
+                    square_polygon("b.png", i * 20, 8),
+                    "lod1".to_string(),
+                );
+            }
+            packer.add_texture("c".to_string(), square_polygon("c.png", 0, 8));
+            packer
+        };
+        let config = TexturePlacerConfig::new(64, 64, 0);
+
+        let packer = new_packer();
+        assert_eq!(packer.create_clusters().len(), 6);
+        let packed = packer.pack(GuillotineTexturePlacer::new(config.clone()));
+        assert_eq!(packed.placed_uv_polygon_map.len(), 6);
+
+        // Everything would fit on one page, but each group gets its own
+        let group_pages = packed.group_pages();
+        assert_eq!(group_pages.len(), 2);
+        assert_eq!(packed.atlases.len(), 3);
+        let mut all_pages = group_pages.values().flatten().copied().collect::<Vec<_>>();
+        let ungrouped = packed.placed_uv_polygon_map[&"c".to_string()].atlas_id;
+        assert_eq!(packed.get_page_group(ungrouped), None);
+        all_pages.push(ungrouped);
+        all_pages.sort();
+        assert_eq!(all_pages, vec![0, 1, 2]);
+        for (group, atlas_ids) in &group_pages {
+            for atlas_id in atlas_ids {
+                for placed in &packed.atlases[atlas_id] {
+                    assert_eq!(
+                        packed.clusters[&placed.cluster_id].group.as_ref(),
+                        Some(group)
+                    );
+                }
+            }
+        }
+
+        // Incremental packing only fills the pages of the same group
+        let mut packed = packed;
+        let mut packer = AtlasPacker::default();
+        packer.add_texture_with_group(
+            "lod2_0".to_string(),
+            square_polygon("d.png", 0, 8),
+            "lod2".to_string(),
+        );
+        let changed = packer
+            .pack_into(&mut packed, GuillotineTexturePlacer::new(config))
+            .unwrap();
+        assert_eq!(changed, group_pages["lod2"]);
+        assert_eq!(packed.group_pages(), group_pages);
+    }
+
+    #[test]
+    fn test_locality_sort() {
+        use crate::place::GuillotineTexturePlacer;
+
+        for curve in [SpaceFillingCurve::Hilbert, SpaceFillingCurve::ZOrder] {
+            // 4x4 buildings of 32px (with the buffer), four of which fill a page
+            let mut packer =
+                AtlasPacker::default().with_sort_strategy(ClusterSortStrategy::Locality(curve));
+            for i in 0..16u32 {
+                let (x, y) = ((i * 7) % 4, (i * 7) / 4 % 4);
+                packer.add_texture(
+                    format!("building_{}_{}", x, y),
+                    square_polygon(&format!("{}.png", i), 0, 28).with_location(
+                        LocationHint::Position(x as f64 * 100.0, y as f64 * 100.0 + 5000.0),
+                    ),
+                );
+            }
+            packer.add_texture("no_hint".to_string(), square_polygon("none.png", 0, 28));
+            let config = TexturePlacerConfig::new(64, 64, 0);
+            let packed = packer.pack(GuillotineTexturePlacer::new(config));
+            assert_eq!(packed.atlases.len(), 5);
+
+            // Every page holds one 2x2 quadrant of the city
+            let mut quadrants = HashSet::new();
+            for atlas_id in 0..4 {
+                let page_quadrants = packed.atlases[&atlas_id]
+                    .iter()
+                    .map(|placed| {
+                        let location = packed.clusters[&placed.cluster_id].location;
+                        let Some(LocationHint::Position(x, y)) = location else {
+                            panic!("missing location");
+                        };
+                        ((x / 200.0) as u32, ((y - 5000.0) / 200.0) as u32)
+                    })
+                    .collect::<HashSet<_>>();
+                assert_eq!(page_quadrants.len(), 1);
+                quadrants.extend(page_quadrants);
+            }
+            assert_eq!(quadrants.len(), 4);
+            assert_eq!(
+                packed.placed_uv_polygon_map[&"no_hint".to_string()].atlas_id,
+                4
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_tolerance_and_cost() {
+        // Two 20px squares side by side with a 4px gap
+        let rect_polygon = |x: u32| {
+            let (min_u, max_u) = (x as f64 / 1024.0, (x + 20) as f64 / 1024.0);
+            let (min_v, max_v) = (1.0 - 20.0 / 1024.0, 1.0);
+            PolygonMappedTexture::new(
+                &PathBuf::from("a.png"),
+                (1024, 1024),
+                &[
+                    (min_u, min_v),
+                    (max_u, min_v),
+                    (max_u, max_v),
+                    (min_u, max_v),
+                ],
+                DownsampleFactor::new(&1.0),
+            )
+        };
+        let cluster_count = |packer: AtlasPacker| {
+            let mut packer = packer;
+            packer.add_texture("a".to_string(), rect_polygon(0));
+            packer.add_texture("b".to_string(), rect_polygon(24));
+            packer.create_clusters().len()
+        };
+
+        assert_eq!(cluster_count(AtlasPacker::default()), 2);
+        assert_eq!(
+            cluster_count(AtlasPacker::default().with_merge_tolerance(3)),
+            2
+        );
+        assert_eq!(
+            cluster_count(AtlasPacker::default().with_merge_tolerance(4)),
+            1
+        );
+
+        // With the buffer, the union (48x24) is exactly as large as both clusters (2 * 24x24)
+        assert_eq!(
+            cluster_count(AtlasPacker::default().with_merge_cost_threshold(1.0)),
+            2
+        );
+        assert_eq!(
+            cluster_count(AtlasPacker::default().with_merge_cost_threshold(1.1)),
+            1
+        );
+
+        // The merged cluster covers both polygons
+        let mut packer = AtlasPacker::default().with_merge_cost_threshold(1.1);
+        packer.add_texture("a".to_string(), rect_polygon(0));
+        packer.add_texture("b".to_string(), rect_polygon(24));
+        let clusters = packer.create_clusters();
+        let cluster = clusters.values().next().unwrap();
+        assert_eq!(cluster.uv_polygons.len(), 2);
+        assert_eq!(cluster.bounding_texture.get_crop_rect(), (0, 0, 44, 20));
+        let mut polygon_ids = packer.clusters().concat();
+        polygon_ids.sort();
+        assert_eq!(polygon_ids, ["a", "b"]);
+
+        // Squares farther apart than their side are still found with a higher threshold: the
+        // union (74x24) is about 1.54 times both clusters
+        let far_apart = |threshold: f64| {
+            let mut packer = AtlasPacker::default().with_merge_cost_threshold(threshold);
+            packer.add_texture("a".to_string(), rect_polygon(0));
+            packer.add_texture("b".to_string(), rect_polygon(50));
+            packer.create_clusters().len()
+        };
+        assert_eq!(far_apart(1.5), 2);
+        assert_eq!(far_apart(1.6), 1);
+
+        // A long row merges step by step into one cluster
+        let mut packer = AtlasPacker::default().with_merge_cost_threshold(1.1);
+        for i in 0..40 {
+            packer.add_texture(i.to_string(), rect_polygon(i * 24));
+        }
+        let clusters = packer.create_clusters();
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters.values().next().unwrap().uv_polygons.len(), 40);
+    }
+
+    #[test]
+    fn test_sparse_split() {
+        // A diagonal chain of overlapping triangles links two far corners into one cluster
+        let chain_packer = |packer: AtlasPacker| {
+            let mut packer = packer;
+            for i in 0..20 {
+                packer.add_texture(i.to_string(), square_polygon("a.png", i * 15, 20));
+            }
+            packer
+        };
+        let buffered_area = |clusters: &HashMap<ClusterID, Cluster>| {
+            clusters
+                .values()
+                .map(|cluster| {
+                    let (_, _, width, height) = cluster.bounding_texture.get_buffered_geometry();
+                    width as u64 * height as u64
+                })
+                .sum::<u64>()
+        };
+
+        let clusters = chain_packer(AtlasPacker::default()).create_clusters();
+        assert_eq!(clusters.len(), 1);
+
+        let split = chain_packer(AtlasPacker::default().with_sparse_split(0.4)).create_clusters();
+        assert!(split.len() > 1);
+        assert!(buffered_area(&split) < buffered_area(&clusters) / 4);
+        let mut polygon_ids = split
+            .values()
+            .flat_map(|cluster| cluster.uv_polygons.iter().map(|(id, _)| id.clone()))
+            .collect::<Vec<_>>();
+        polygon_ids.sort();
+        polygon_ids.dedup();
+        assert_eq!(polygon_ids.len(), 20);
+
+        // Dense clusters are kept
+        let mut packer = AtlasPacker::default().with_sparse_split(0.4);
+        packer.add_texture("a".to_string(), square_polygon("a.png", 0, 20));
+        packer.add_texture("b".to_string(), square_polygon("a.png", 0, 20));
+        assert_eq!(packer.create_clusters().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "between 0 and 1")]
+    fn test_sparse_split_out_of_range() {
+        let _ = AtlasPacker::default().with_sparse_split(1.5);
+    }
+
+    #[test]
+    fn test_exact_overlap() {
+        // Parallel diagonal strips: their bounding boxes overlap, the strips are ~21px apart
+        let strip_polygon = |x: u32| {
+            let uv = |(px, py): (u32, u32)| (px as f64 / 1024.0, 1.0 - py as f64 / 1024.0);
+            PolygonMappedTexture::new(
+                &PathBuf::from("a.png"),
+                (1024, 1024),
+                &[
+                    uv((x, 0)),
+                    uv((x + 10, 0)),
+                    uv((x + 110, 100)),
+                    uv((x + 100, 100)),
+                ],
+                DownsampleFactor::new(&1.0),
+            )
+        };
+        let cluster_count = |packer: AtlasPacker, offsets: &[u32]| {
+            let mut packer = packer;
+            for (i, &x) in offsets.iter().enumerate() {
+                packer.add_texture(i.to_string(), strip_polygon(x));
+            }
+            packer.create_clusters().len()
+        };
+
+        assert_eq!(cluster_count(AtlasPacker::default(), &[0, 40]), 1);
+        let exact = || AtlasPacker::default().with_exact_overlap(true);
+        assert_eq!(cluster_count(exact(), &[0, 40]), 2);
+        // The buffers (2px each) and the tolerance grow the polygons
+        assert_eq!(cluster_count(exact().with_merge_tolerance(18), &[0, 40]), 1);
+        assert_eq!(cluster_count(exact().with_merge_tolerance(18), &[0, 60]), 2);
+        // Overlapping strips are still merged
+        assert_eq!(cluster_count(exact(), &[0, 5]), 1);
+        // Exact mode only confirms bounding box hits, so boxes 3px apart stay apart in both modes
+        assert_eq!(cluster_count(AtlasPacker::default(), &[0, 113]), 2);
+        assert_eq!(cluster_count(exact(), &[0, 113]), 2);
+    }
 }
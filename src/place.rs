@@ -1,17 +1,46 @@
-use std::collections::HashMap;
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use crate::{
-    texture::{ChildUVPolygon, ClusterBoundingTexture},
+    texture::{utils::is_point_inside_polygon, ChildUVPolygon, ClusterBoundingTexture},
     AtlasID, ClusterID, PolygonID,
 };
 
+/// How page sizes are rounded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeRounding {
+    /// Round up to the next power of two
+    #[default]
+    PowerOfTwo,
+    /// Keep the exact size
+    Exact,
+    /// Round up to a multiple of N (e.g. 4 for block-compressed formats)
+    MultipleOf(u32),
+}
+
+impl SizeRounding {
+    pub fn round(&self, size: u32) -> u32 {
+        match self {
+            SizeRounding::PowerOfTwo => size.checked_next_power_of_two().unwrap(),
+            SizeRounding::Exact => size,
+            SizeRounding::MultipleOf(n) => size.div_ceil((*n).max(1)) * (*n).max(1),
+        }
+    }
+}
+
+// Built with the constructors and `with_*` methods, so that new options do not break callers
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct TexturePlacerConfig {
     pub width: u32,
     pub height: u32,
     pub padding: u32,
+    // Allow placers to rotate textures by 90 degrees (clockwise)
+    pub allow_rotation: bool,
+    // Rounding of the page sizes (also used when pages are shrunk or enlarged)
+    pub size_rounding: SizeRounding,
     // and more option
-    // Allow rotation, allow multiple pages, adjust resolution, specify resampling method, etc...
+    // allow multiple pages, adjust resolution, specify resampling method, etc...
 }
 
 impl Default for TexturePlacerConfig {
@@ -20,6 +49,8 @@ impl Default for TexturePlacerConfig {
             width: 1024,
             height: 1024,
             padding: 0,
+            allow_rotation: false,
+            size_rounding: SizeRounding::PowerOfTwo,
         }
     }
 }
@@ -27,13 +58,36 @@ impl Default for TexturePlacerConfig {
 impl TexturePlacerConfig {
     // Ensure that the width and height are powers of two
     pub fn new(width: u32, height: u32, padding: u32) -> Self {
+        Self::new_with_rounding(width, height, padding, SizeRounding::PowerOfTwo)
+    }
+
+    pub fn new_with_rounding(
+        width: u32,
+        height: u32,
+        padding: u32,
+        size_rounding: SizeRounding,
+    ) -> Self {
         TexturePlacerConfig {
-            width: width.checked_next_power_of_two().unwrap(),
-            height: height.checked_next_power_of_two().unwrap(),
+            width: size_rounding.round(width),
+            height: size_rounding.round(height),
             padding,
+            allow_rotation: false,
+            size_rounding,
         }
     }
 
+    pub fn with_rotation(mut self, allow_rotation: bool) -> Self {
+        self.allow_rotation = allow_rotation;
+        self
+    }
+
+    pub fn with_size_rounding(mut self, size_rounding: SizeRounding) -> Self {
+        self.width = size_rounding.round(self.width);
+        self.height = size_rounding.round(self.height);
+        self.size_rounding = size_rounding;
+        self
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -45,19 +99,103 @@ impl TexturePlacerConfig {
     pub fn padding(&self) -> u32 {
         self.padding
     }
+
+    pub fn allow_rotation(&self) -> bool {
+        self.allow_rotation
+    }
+
+    pub fn size_rounding(&self) -> SizeRounding {
+        self.size_rounding
+    }
+
+    // Smallest page size that holds `width` x `height` pixels
+    pub(crate) fn fit_page_size(&self, width: u32, height: u32) -> (u32, u32) {
+        (
+            self.size_rounding.round(width.max(1)),
+            self.size_rounding.round(height.max(1)),
+        )
+    }
+
+    // Candidate (width, height, rotated) footprints of a texture
+    fn orientations(&self, width: u32, height: u32) -> Vec<(u32, u32, bool)> {
+        if self.allow_rotation && width != height {
+            vec![(width, height, false), (height, width, true)]
+        } else {
+            vec![(width, height, false)]
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+// Placers outside this crate build it with `new` and the `with_*` methods
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct PlacedTextureGeometry {
     pub cluster_id: ClusterID,
     pub atlas_id: AtlasID,
     // Pixel coordinates on atlas
     pub origin: (u32, u32),
+    // Size on atlas (after rotation)
     pub width: u32,
     pub height: u32,
+    // Rotated by 90 degrees clockwise
+    pub rotated: bool,
+    // Texels to write when bounding boxes may overlap on the atlas (None: the whole box)
+    pub mask: Option<PlacementMask>,
 }
 
-#[derive(Debug, Clone)]
+impl PlacedTextureGeometry {
+    pub fn new(
+        cluster_id: ClusterID,
+        atlas_id: AtlasID,
+        origin: (u32, u32),
+        width: u32,
+        height: u32,
+    ) -> Self {
+        PlacedTextureGeometry {
+            cluster_id,
+            atlas_id,
+            origin,
+            width,
+            height,
+            rotated: false,
+            mask: None,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotated: bool) -> Self {
+        self.rotated = rotated;
+        self
+    }
+
+    pub fn with_mask(mut self, mask: PlacementMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+}
+
+/// Coarse grid of the cells of a placed texture that hold polygon texels (after rotation)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlacementMask {
+    pub cell_size: u32,
+    pub columns: u32,
+    pub rows: u32,
+    pub cells: Vec<bool>,
+}
+
+impl PlacementMask {
+    // Whether the pixel of the placed texture at (x, y) is covered
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        let (column, row) = (x / self.cell_size, y / self.cell_size);
+        column < self.columns
+            && row < self.rows
+            && self.cells[(row * self.columns + column) as usize]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlacedUVPolygon {
     pub polygon_id: PolygonID,
     pub cluster_id: ClusterID,
@@ -79,8 +217,32 @@ pub trait TexturePlacer: Send + Sync {
 
     fn can_place(&self, texture: &ClusterBoundingTexture) -> bool;
 
+    // Placers that look at the polygon shapes override this; the others only need the bounding box
+    fn can_place_cluster(
+        &self,
+        texture: &ClusterBoundingTexture,
+        _children: &[(PolygonID, ChildUVPolygon)],
+    ) -> bool {
+        self.can_place(texture)
+    }
+
     fn reset_param(&mut self);
 
+    // Mark an existing placement (and its padding) as used, so that later placements go around
+    // it. Placers that cannot do this return false.
+    fn reserve(&mut self, _placed: &PlacedTextureGeometry) -> bool {
+        false
+    }
+
+    // Empty placer with the same configuration, so that another page can be open at the same
+    // time. Without it, a new page takes the placer over from the last open page.
+    fn new_page(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
     fn scale_dimensions(&self, width: u32, height: u32, downsample_factor: f32) -> (u32, u32) {
         let scaled_width = (width as f32 * downsample_factor).max(1.0) as u32;
         let scaled_height = (height as f32 * downsample_factor).max(1.0) as u32;
@@ -88,6 +250,126 @@ pub trait TexturePlacer: Send + Sync {
     }
 }
 
+/// Object-safe clone of a placer, so that different placers can be mixed in one list
+pub trait DynTexturePlacer: TexturePlacer {
+    fn clone_box(&self) -> Box<dyn DynTexturePlacer>;
+
+    // A placer that is already boxed is returned as is instead of being boxed again
+    fn into_box(self) -> Box<dyn DynTexturePlacer>
+    where
+        Self: Sized;
+}
+
+impl<T: TexturePlacer + Clone + 'static> DynTexturePlacer for T {
+    fn clone_box(&self) -> Box<dyn DynTexturePlacer> {
+        self.clone().into_box()
+    }
+
+    fn into_box(self) -> Box<dyn DynTexturePlacer> {
+        let mut placer = Some(self);
+        if let Some(boxed) =
+            (&mut placer as &mut dyn Any).downcast_mut::<Option<Box<dyn DynTexturePlacer>>>()
+        {
+            return boxed.take().unwrap();
+        }
+        Box::new(placer.unwrap())
+    }
+}
+
+impl Clone for Box<dyn DynTexturePlacer> {
+    fn clone(&self) -> Self {
+        self.as_ref().clone_box()
+    }
+}
+
+impl TexturePlacer for Box<dyn DynTexturePlacer> {
+    fn config(&self) -> &TexturePlacerConfig {
+        self.as_ref().config()
+    }
+
+    fn place_texture(
+        &mut self,
+        bounding_texture: ClusterBoundingTexture,
+        children: Vec<(PolygonID, ChildUVPolygon)>,
+        cluster_id: ClusterID,
+        parent_atlas_id: AtlasID,
+    ) -> (PlacedTextureGeometry, Vec<Option<PlacedUVPolygon>>) {
+        self.as_mut()
+            .place_texture(bounding_texture, children, cluster_id, parent_atlas_id)
+    }
+
+    fn can_place(&self, texture: &ClusterBoundingTexture) -> bool {
+        self.as_ref().can_place(texture)
+    }
+
+    fn can_place_cluster(
+        &self,
+        texture: &ClusterBoundingTexture,
+        children: &[(PolygonID, ChildUVPolygon)],
+    ) -> bool {
+        self.as_ref().can_place_cluster(texture, children)
+    }
+
+    fn reserve(&mut self, placed: &PlacedTextureGeometry) -> bool {
+        self.as_mut().reserve(placed)
+    }
+
+    fn new_page(&self) -> Option<Self> {
+        let mut placer = self.clone_box();
+        placer.reset_param();
+        Some(placer)
+    }
+
+    fn reset_param(&mut self) {
+        self.as_mut().reset_param()
+    }
+
+    fn scale_dimensions(&self, width: u32, height: u32, downsample_factor: f32) -> (u32, u32) {
+        self.as_ref()
+            .scale_dimensions(width, height, downsample_factor)
+    }
+}
+
+/// Convert the cropped UV coordinates of the children into UV coordinates on the atlas
+pub(crate) fn place_children(
+    config: &TexturePlacerConfig,
+    placed: &PlacedTextureGeometry,
+    children: &[(PolygonID, ChildUVPolygon)],
+) -> Vec<Option<PlacedUVPolygon>> {
+    children
+        .iter()
+        .map(|(polygon_id, uv_polygon)| {
+            let placed_uv_coords = uv_polygon
+                .cropped_uv_coords
+                .iter()
+                .map(|&(u, v)| {
+                    let (x, y) = if placed.rotated {
+                        // (x, y) -> (height - y, x) on the texture before rotation
+                        let x = (u * placed.height as f64) as u32;
+                        let y = ((1.0 - v) * placed.width as f64) as u32;
+                        (placed.width - y, x)
+                    } else {
+                        let x = (u * placed.width as f64) as u32;
+                        let y = ((1.0 - v) * placed.height as f64) as u32;
+                        (x, y)
+                    };
+                    (
+                        (placed.origin.0 as f64 + x as f64) / config.width as f64,
+                        1.0 - ((placed.origin.1 as f64 + y as f64) / config.height as f64),
+                    )
+                })
+                .collect::<Vec<(f64, f64)>>();
+            Some(PlacedUVPolygon {
+                polygon_id: polygon_id.clone(),
+                cluster_id: placed.cluster_id.clone(),
+                atlas_id: placed.atlas_id,
+                placed_uv_coords,
+            })
+        })
+        .collect()
+}
+
+#[derive(Clone)]
 pub struct GuillotineTexturePlacer {
     config: TexturePlacerConfig,
     free_rects: Vec<Rect>,
@@ -102,6 +384,71 @@ struct Rect {
     height: u32,
 }
 
+impl Rect {
+    #[inline]
+    fn right(&self) -> u32 {
+        self.x + self.width
+    }
+
+    #[inline]
+    fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    fn contains(&self, other: &Rect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    #[inline]
+    fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    // Area taken by a placement, including the padding before it
+    fn reserved(config: &TexturePlacerConfig, placed: &PlacedTextureGeometry) -> Rect {
+        let x = placed.origin.0.saturating_sub(config.padding);
+        let y = placed.origin.1.saturating_sub(config.padding);
+        Rect {
+            x,
+            y,
+            width: placed.origin.0 + placed.width - x,
+            height: placed.origin.1 + placed.height - y,
+        }
+    }
+
+    // The rectangle covering both, if `other` continues this one downwards or to the right
+    fn merge(&self, other: &Rect) -> Option<Rect> {
+        if self.x == other.x && self.width == other.width && self.y + self.height == other.y {
+            Some(Rect {
+                x: self.x,
+                y: self.y,
+                width: self.width,
+                height: self.height + other.height,
+            })
+        } else if self.y == other.y && self.height == other.height && self.x + self.width == other.x
+        {
+            Some(Rect {
+                x: self.x,
+                y: self.y,
+                width: self.width + other.width,
+                height: self.height,
+            })
+        } else {
+            None
+        }
+    }
+}
+
 impl GuillotineTexturePlacer {
     pub fn new(config: TexturePlacerConfig) -> Self {
         let initial_rect = Rect {
@@ -117,12 +464,18 @@ impl GuillotineTexturePlacer {
         }
     }
 
-    fn find_best_rect(&self, width: u32, height: u32) -> Option<Rect> {
-        self.free_rects
-            .iter()
-            .filter(|&rect| rect.width >= width && rect.height >= height)
-            .min_by_key(|&rect| rect.width * rect.height)
-            .cloned()
+    fn find_best_rect(&self, width: u32, height: u32) -> Option<(Rect, bool)> {
+        self.config
+            .orientations(width, height)
+            .into_iter()
+            .filter_map(|(width, height, rotated)| {
+                self.free_rects
+                    .iter()
+                    .filter(|&rect| rect.width >= width && rect.height >= height)
+                    .min_by_key(|&rect| rect.width * rect.height)
+                    .map(|&rect| (rect, rotated))
+            })
+            .min_by_key(|(rect, _)| rect.width * rect.height)
     }
 
     fn split_rect(&mut self, rect: Rect, placed: &PlacedTextureGeometry) {
@@ -175,7 +528,7 @@ impl GuillotineTexturePlacer {
             let mut j = i + 1;
             while j < self.free_rects.len() {
                 let rect2 = self.free_rects[j];
-                if let Some(merged_rect) = Self::try_merge_rects(rect1, rect2) {
+                if let Some(merged_rect) = rect1.merge(&rect2) {
                     self.free_rects[i] = merged_rect;
                     self.free_rects.swap_remove(j);
                     merged = true;
@@ -188,50 +541,6 @@ impl GuillotineTexturePlacer {
             }
         }
     }
-
-    fn try_merge_rects(rect1: Rect, rect2: Rect) -> Option<Rect> {
-        if rect1.x == rect2.x && rect1.width == rect2.width && rect1.y + rect1.height == rect2.y {
-            Some(Rect {
-                x: rect1.x,
-                y: rect1.y,
-                width: rect1.width,
-                height: rect1.height + rect2.height,
-            })
-        } else if rect1.y == rect2.y
-            && rect1.height == rect2.height
-            && rect1.x + rect1.width == rect2.x
-        {
-            Some(Rect {
-                x: rect1.x,
-                y: rect1.y,
-                width: rect1.width + rect2.width,
-                height: rect1.height,
-            })
-        } else {
-            None
-        }
-    }
-
-    fn cropped_uv_to_placed_uv(
-        &self,
-        rect: Rect,
-        uv: (f64, f64),
-        width: u32,
-        height: u32,
-    ) -> (f64, f64) {
-        let (x, y) = self.uv_to_pixel(uv, width, height);
-        (
-            (rect.x as f64 + self.config.padding as f64 + x as f64) / self.config.width as f64,
-            1.0 - ((rect.y as f64 + self.config.padding as f64 + y as f64)
-                / self.config.height as f64),
-        )
-    }
-
-    fn uv_to_pixel(&self, uv: (f64, f64), width: u32, height: u32) -> (u32, u32) {
-        let x = (uv.0 * width as f64) as u32;
-        let y = ((1.0 - uv.1) * height as f64) as u32;
-        (x, y)
-    }
 }
 
 impl TexturePlacer for GuillotineTexturePlacer {
@@ -253,36 +562,26 @@ impl TexturePlacer for GuillotineTexturePlacer {
             bounding_texture.downsample_factor.value(),
         );
 
-        if let Some(rect) = self.find_best_rect(
+        if let Some((rect, rotated)) = self.find_best_rect(
             scaled_width + self.config.padding,
             scaled_height + self.config.padding,
         ) {
+            let (width, height) = if rotated {
+                (scaled_height, scaled_width)
+            } else {
+                (scaled_width, scaled_height)
+            };
             let bounding_placed = PlacedTextureGeometry {
                 cluster_id: cluster_id.clone(),
                 atlas_id: parent_atlas_id,
                 origin: (rect.x + self.config.padding, rect.y + self.config.padding),
-                width: scaled_width,
-                height: scaled_height,
+                width,
+                height,
+                rotated,
+                mask: None,
             };
 
-            let children_placed = children
-                .iter()
-                .map(|(polygon_id, uv_polygon)| {
-                    let placed_uv_coords = uv_polygon
-                        .cropped_uv_coords
-                        .iter()
-                        .map(|&(u, v)| {
-                            self.cropped_uv_to_placed_uv(rect, (u, v), scaled_width, scaled_height)
-                        })
-                        .collect::<Vec<(f64, f64)>>();
-                    Some(PlacedUVPolygon {
-                        polygon_id: polygon_id.clone(),
-                        cluster_id: cluster_id.clone(),
-                        atlas_id: parent_atlas_id,
-                        placed_uv_coords,
-                    })
-                })
-                .collect::<Vec<Option<PlacedUVPolygon>>>();
+            let children_placed = place_children(&self.config, &bounding_placed, &children);
 
             self.used_rects.insert(cluster_id, bounding_placed.clone());
             self.free_rects.retain(|r| r != &rect);
@@ -290,7 +589,7 @@ impl TexturePlacer for GuillotineTexturePlacer {
             self.merge_free_rects();
             (bounding_placed, children_placed)
         } else {
-            // todo: Consideration of processing when the texture is larger than the atlas size
+            // The packer handles the textures larger than an empty atlas before placing them
             panic!("Texture could not be placed: {}", cluster_id);
         }
     }
@@ -302,11 +601,61 @@ impl TexturePlacer for GuillotineTexturePlacer {
             buffered_height,
             texture.downsample_factor.value(),
         );
-        let width = scaled_width + self.config.padding;
-        let height = scaled_height + self.config.padding;
-        self.free_rects
-            .iter()
-            .any(|r| r.width >= width && r.height >= height)
+        self.find_best_rect(
+            scaled_width + self.config.padding,
+            scaled_height + self.config.padding,
+        )
+        .is_some()
+    }
+
+    fn reserve(&mut self, placed: &PlacedTextureGeometry) -> bool {
+        let used = Rect::reserved(&self.config, placed);
+        let mut free_rects = Vec::with_capacity(self.free_rects.len() + 3);
+        for rect in self.free_rects.drain(..) {
+            if !rect.intersects(&used) {
+                free_rects.push(rect);
+                continue;
+            }
+            // The parts left, right, above and below the used area stay disjoint
+            let (left, right) = (used.x.max(rect.x), used.right().min(rect.right()));
+            let (top, bottom) = (used.y.max(rect.y), used.bottom().min(rect.bottom()));
+            let parts = [
+                Rect {
+                    x: rect.x,
+                    y: rect.y,
+                    width: left - rect.x,
+                    height: rect.height,
+                },
+                Rect {
+                    x: right,
+                    y: rect.y,
+                    width: rect.right() - right,
+                    height: rect.height,
+                },
+                Rect {
+                    x: left,
+                    y: rect.y,
+                    width: right - left,
+                    height: top - rect.y,
+                },
+                Rect {
+                    x: left,
+                    y: bottom,
+                    width: right - left,
+                    height: rect.bottom() - bottom,
+                },
+            ];
+            free_rects.extend(parts.into_iter().filter(|part| part.area() > 0));
+        }
+        self.free_rects = free_rects;
+        self.merge_free_rects();
+        self.used_rects
+            .insert(placed.cluster_id.clone(), placed.clone());
+        true
+    }
+
+    fn new_page(&self) -> Option<Self> {
+        Some(Self::new(self.config.clone()))
     }
 
     fn reset_param(&mut self) {
@@ -320,3 +669,1657 @@ impl TexturePlacer for GuillotineTexturePlacer {
         self.used_rects.clear();
     }
 }
+
+/// Rule used by the MaxRects placer to choose a free rectangle for a texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaxRectsHeuristic {
+    /// Minimize the shorter leftover side of the free rectangle
+    #[default]
+    BestShortSideFit,
+    /// Minimize the longer leftover side of the free rectangle
+    BestLongSideFit,
+    /// Minimize the leftover area of the free rectangle
+    BestAreaFit,
+    /// Place as close to the top-left corner as possible (Tetris-like)
+    BottomLeft,
+    /// Maximize the length of the edges touching placed textures and the atlas border
+    ContactPoint,
+}
+
+/// MaxRects algorithm
+/// - keeps every maximal free rectangle, so free rectangles may overlap
+/// - less fragmentation than the guillotine splits at the cost of more bookkeeping
+#[derive(Clone)]
+pub struct MaxRectsTexturePlacer {
+    config: TexturePlacerConfig,
+    heuristic: MaxRectsHeuristic,
+    free_rects: Vec<Rect>,
+    used_rects: Vec<Rect>,
+}
+
+impl MaxRectsTexturePlacer {
+    pub fn new(config: TexturePlacerConfig, heuristic: MaxRectsHeuristic) -> Self {
+        let initial_rect = Rect {
+            x: 0,
+            y: 0,
+            width: config.width,
+            height: config.height,
+        };
+        MaxRectsTexturePlacer {
+            config,
+            heuristic,
+            free_rects: vec![initial_rect],
+            used_rects: Vec::new(),
+        }
+    }
+
+    pub fn heuristic(&self) -> MaxRectsHeuristic {
+        self.heuristic
+    }
+
+    // Lower scores are better; the second value breaks ties
+    fn score(&self, free_rect: &Rect, width: u32, height: u32) -> (i64, i64) {
+        let leftover_x = (free_rect.width - width) as i64;
+        let leftover_y = (free_rect.height - height) as i64;
+        match self.heuristic {
+            MaxRectsHeuristic::BestShortSideFit => {
+                (leftover_x.min(leftover_y), leftover_x.max(leftover_y))
+            }
+            MaxRectsHeuristic::BestLongSideFit => {
+                (leftover_x.max(leftover_y), leftover_x.min(leftover_y))
+            }
+            MaxRectsHeuristic::BestAreaFit => (
+                free_rect.width as i64 * free_rect.height as i64 - width as i64 * height as i64,
+                leftover_x.min(leftover_y),
+            ),
+            MaxRectsHeuristic::BottomLeft => ((free_rect.y + height) as i64, free_rect.x as i64),
+            MaxRectsHeuristic::ContactPoint => {
+                let candidate = Rect {
+                    x: free_rect.x,
+                    y: free_rect.y,
+                    width,
+                    height,
+                };
+                (-(self.contact_score(&candidate) as i64), 0)
+            }
+        }
+    }
+
+    fn contact_score(&self, rect: &Rect) -> u32 {
+        fn overlap(start_0: u32, end_0: u32, start_1: u32, end_1: u32) -> u32 {
+            end_0.min(end_1).saturating_sub(start_0.max(start_1))
+        }
+
+        let mut score = 0;
+        if rect.x == 0 || rect.right() == self.config.width {
+            score += rect.height;
+        }
+        if rect.y == 0 || rect.bottom() == self.config.height {
+            score += rect.width;
+        }
+        for used in &self.used_rects {
+            if used.x == rect.right() || used.right() == rect.x {
+                score += overlap(used.y, used.bottom(), rect.y, rect.bottom());
+            }
+            if used.y == rect.bottom() || used.bottom() == rect.y {
+                score += overlap(used.x, used.right(), rect.x, rect.right());
+            }
+        }
+        score
+    }
+
+    fn find_best_rect(&self, width: u32, height: u32) -> Option<(Rect, bool)> {
+        self.config
+            .orientations(width, height)
+            .into_iter()
+            .flat_map(|(width, height, rotated)| {
+                self.free_rects
+                    .iter()
+                    .filter(move |rect| rect.width >= width && rect.height >= height)
+                    .map(move |rect| {
+                        (
+                            Rect {
+                                x: rect.x,
+                                y: rect.y,
+                                width,
+                                height,
+                            },
+                            rotated,
+                            self.score(rect, width, height),
+                        )
+                    })
+            })
+            .min_by_key(|(_, _, score)| *score)
+            .map(|(rect, rotated, _)| (rect, rotated))
+    }
+
+    fn occupy(&mut self, used: Rect) {
+        let mut new_free_rects = Vec::with_capacity(self.free_rects.len() + 4);
+        for free in self.free_rects.drain(..) {
+            if !free.intersects(&used) {
+                new_free_rects.push(free);
+                continue;
+            }
+            // Split the free rectangle into the (up to four) maximal parts outside the used one
+            if used.x > free.x {
+                new_free_rects.push(Rect {
+                    width: used.x - free.x,
+                    ..free
+                });
+            }
+            if used.right() < free.right() {
+                new_free_rects.push(Rect {
+                    x: used.right(),
+                    width: free.right() - used.right(),
+                    ..free
+                });
+            }
+            if used.y > free.y {
+                new_free_rects.push(Rect {
+                    height: used.y - free.y,
+                    ..free
+                });
+            }
+            if used.bottom() < free.bottom() {
+                new_free_rects.push(Rect {
+                    y: used.bottom(),
+                    height: free.bottom() - used.bottom(),
+                    ..free
+                });
+            }
+        }
+        self.free_rects = new_free_rects;
+        self.prune_free_rects();
+        self.used_rects.push(used);
+    }
+
+    // Remove the free rectangles that are contained in another one
+    fn prune_free_rects(&mut self) {
+        let mut i = 0;
+        while i < self.free_rects.len() {
+            let mut removed = false;
+            let mut j = i + 1;
+            while j < self.free_rects.len() {
+                if self.free_rects[j].contains(&self.free_rects[i]) {
+                    self.free_rects.swap_remove(i);
+                    removed = true;
+                    break;
+                }
+                if self.free_rects[i].contains(&self.free_rects[j]) {
+                    self.free_rects.swap_remove(j);
+                } else {
+                    j += 1;
+                }
+            }
+            if !removed {
+                i += 1;
+            }
+        }
+    }
+}
+
+impl TexturePlacer for MaxRectsTexturePlacer {
+    fn config(&self) -> &TexturePlacerConfig {
+        &self.config
+    }
+
+    fn place_texture(
+        &mut self,
+        bounding_texture: ClusterBoundingTexture,
+        children: Vec<(PolygonID, ChildUVPolygon)>,
+        cluster_id: ClusterID,
+        parent_atlas_id: AtlasID,
+    ) -> (PlacedTextureGeometry, Vec<Option<PlacedUVPolygon>>) {
+        let (_, _, buffered_width, buffered_height) = bounding_texture.get_buffered_geometry();
+        let (scaled_width, scaled_height) = self.scale_dimensions(
+            buffered_width,
+            buffered_height,
+            bounding_texture.downsample_factor.value(),
+        );
+
+        let Some((rect, rotated)) = self.find_best_rect(
+            scaled_width + self.config.padding,
+            scaled_height + self.config.padding,
+        ) else {
+            panic!("Texture could not be placed: {}", cluster_id);
+        };
+
+        let bounding_placed = PlacedTextureGeometry {
+            cluster_id,
+            atlas_id: parent_atlas_id,
+            origin: (rect.x + self.config.padding, rect.y + self.config.padding),
+            width: rect.width - self.config.padding,
+            height: rect.height - self.config.padding,
+            rotated,
+            mask: None,
+        };
+        let children_placed = place_children(&self.config, &bounding_placed, &children);
+
+        self.occupy(rect);
+        (bounding_placed, children_placed)
+    }
+
+    fn can_place(&self, texture: &ClusterBoundingTexture) -> bool {
+        let (_, _, buffered_width, buffered_height) = texture.get_buffered_geometry();
+        let (scaled_width, scaled_height) = self.scale_dimensions(
+            buffered_width,
+            buffered_height,
+            texture.downsample_factor.value(),
+        );
+        self.find_best_rect(
+            scaled_width + self.config.padding,
+            scaled_height + self.config.padding,
+        )
+        .is_some()
+    }
+
+    fn reserve(&mut self, placed: &PlacedTextureGeometry) -> bool {
+        self.occupy(Rect::reserved(&self.config, placed));
+        true
+    }
+
+    fn new_page(&self) -> Option<Self> {
+        Some(Self::new(self.config.clone(), self.heuristic))
+    }
+
+    fn reset_param(&mut self) {
+        let initial_rect = Rect {
+            x: 0,
+            y: 0,
+            width: self.config.width,
+            height: self.config.height,
+        };
+        self.free_rects = vec![initial_rect];
+        self.used_rects.clear();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SkylineNode {
+    x: u32,
+    // Top of the free space above this segment (top-left origin)
+    y: u32,
+    width: u32,
+}
+
+// Number of gaps kept in the waste map; the smallest ones are forgotten first
+const MAX_WASTE_RECTS: usize = 128;
+
+/// Skyline algorithm
+/// - keeps only the upper contour of the placed textures, so the placement cost does not grow with the number of placed textures
+/// - optionally keeps the gaps left below the contour (waste map) and reuses them for later textures
+#[derive(Clone)]
+pub struct SkylineTexturePlacer {
+    config: TexturePlacerConfig,
+    use_waste_map: bool,
+    skyline: Vec<SkylineNode>,
+    waste_rects: Vec<Rect>,
+}
+
+impl SkylineTexturePlacer {
+    pub fn new(config: TexturePlacerConfig, use_waste_map: bool) -> Self {
+        let initial_node = SkylineNode {
+            x: 0,
+            y: 0,
+            width: config.width,
+        };
+        SkylineTexturePlacer {
+            config,
+            use_waste_map,
+            skyline: vec![initial_node],
+            waste_rects: Vec::new(),
+        }
+    }
+
+    // Bottom-left rule: the lowest bottom edge wins, then the narrowest node. A texture starting
+    // at a node rests on the highest node below it, which a window sliding over the nodes tracks
+    // in one pass.
+    fn find_skyline_position(&self, width: u32, height: u32) -> Option<(usize, Rect)> {
+        let mut best: Option<(usize, Rect)> = None;
+        // Nodes in [start, end) are under the texture; `highest` keeps their indices by
+        // decreasing y
+        let mut highest: VecDeque<usize> = VecDeque::new();
+        let (mut end, mut covered) = (0, 0u64);
+        for (start, node) in self.skyline.iter().enumerate() {
+            if node.x as u64 + width as u64 > self.config.width as u64 {
+                break;
+            }
+            while covered < width as u64 {
+                let y = self.skyline[end].y;
+                while highest
+                    .back()
+                    .is_some_and(|&index| self.skyline[index].y <= y)
+                {
+                    highest.pop_back();
+                }
+                highest.push_back(end);
+                covered += self.skyline[end].width as u64;
+                end += 1;
+            }
+            while highest.front().is_some_and(|&index| index < start) {
+                highest.pop_front();
+            }
+
+            let y = self.skyline[highest[0]].y;
+            if y + height <= self.config.height {
+                let rect = Rect {
+                    x: node.x,
+                    y,
+                    width,
+                    height,
+                };
+                let key = (rect.bottom(), node.width);
+                if best.is_none_or(|(index, best)| key < (best.bottom(), self.skyline[index].width))
+                {
+                    best = Some((start, rect));
+                }
+            }
+            covered -= node.width as u64;
+        }
+        best
+    }
+
+    fn find_waste_rect(&self, width: u32, height: u32) -> Option<usize> {
+        if !self.use_waste_map {
+            return None;
+        }
+        self.waste_rects
+            .iter()
+            .enumerate()
+            .filter(|(_, rect)| rect.width >= width && rect.height >= height)
+            .min_by_key(|(_, rect)| rect.width * rect.height)
+            .map(|(index, _)| index)
+    }
+
+    // Merge the gap with the ones sharing a whole edge, and drop the smallest gap when the map is
+    // full
+    fn add_waste_rect(&mut self, mut rect: Rect) {
+        while let Some((index, merged)) =
+            self.waste_rects
+                .iter()
+                .enumerate()
+                .find_map(|(index, other)| {
+                    rect.merge(other)
+                        .or_else(|| other.merge(&rect))
+                        .map(|merged| (index, merged))
+                })
+        {
+            self.waste_rects.swap_remove(index);
+            rect = merged;
+        }
+
+        if self.waste_rects.len() < MAX_WASTE_RECTS {
+            self.waste_rects.push(rect);
+        } else if let Some((index, smallest)) = self
+            .waste_rects
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, other)| other.area())
+        {
+            if smallest.area() < rect.area() {
+                self.waste_rects[index] = rect;
+            }
+        }
+    }
+
+    fn place_in_waste_rect(&mut self, index: usize, width: u32, height: u32) -> Rect {
+        let free = self.waste_rects.swap_remove(index);
+        // Guillotine split along the shorter leftover axis
+        let (right, bottom) = if free.width - width < free.height - height {
+            (
+                Rect {
+                    x: free.x + width,
+                    y: free.y,
+                    width: free.width - width,
+                    height,
+                },
+                Rect {
+                    x: free.x,
+                    y: free.y + height,
+                    width: free.width,
+                    height: free.height - height,
+                },
+            )
+        } else {
+            (
+                Rect {
+                    x: free.x + width,
+                    y: free.y,
+                    width: free.width - width,
+                    height: free.height,
+                },
+                Rect {
+                    x: free.x,
+                    y: free.y + height,
+                    width,
+                    height: free.height - height,
+                },
+            )
+        };
+        for rect in [right, bottom] {
+            if rect.width > 0 && rect.height > 0 {
+                self.add_waste_rect(rect);
+            }
+        }
+        Rect {
+            x: free.x,
+            y: free.y,
+            width,
+            height,
+        }
+    }
+
+    fn add_skyline_level(&mut self, index: usize, rect: Rect) {
+        // Record the gaps between the old contour and the bottom of the new texture
+        if self.use_waste_map {
+            let mut gaps = Vec::new();
+            for node in &self.skyline[index..] {
+                if node.x >= rect.right() {
+                    break;
+                }
+                let right = node.x.saturating_add(node.width).min(rect.right());
+                if node.y < rect.y && right > node.x {
+                    gaps.push(Rect {
+                        x: node.x,
+                        y: node.y,
+                        width: right - node.x,
+                        height: rect.y - node.y,
+                    });
+                }
+            }
+            for gap in gaps {
+                self.add_waste_rect(gap);
+            }
+        }
+
+        let new_node = SkylineNode {
+            x: rect.x,
+            y: rect.bottom(),
+            width: rect.width,
+        };
+        self.skyline.insert(index, new_node);
+
+        // Shrink or remove the nodes now covered by the new one
+        let i = index + 1;
+        while i < self.skyline.len() {
+            let node = self.skyline[i];
+            if node.x >= rect.right() {
+                break;
+            }
+            let shrink = rect.right() - node.x;
+            if shrink >= node.width {
+                self.skyline.remove(i);
+            } else {
+                self.skyline[i].x += shrink;
+                self.skyline[i].width -= shrink;
+                break;
+            }
+        }
+
+        // Merge the new node with its neighbours at the same level; the others were merged already
+        let mut i = index.saturating_sub(1);
+        while i + 1 < self.skyline.len() && i <= index {
+            if self.skyline[i].y == self.skyline[i + 1].y {
+                self.skyline[i].width += self.skyline[i + 1].width;
+                self.skyline.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    // The waste map is tried first, then the skyline
+    fn find_placement(&self, width: u32, height: u32) -> Option<(SkylineSlot, bool)> {
+        let orientations = self.config.orientations(width, height);
+
+        let waste = orientations
+            .iter()
+            .filter_map(|&(width, height, rotated)| {
+                self.find_waste_rect(width, height)
+                    .map(|index| (index, width, height, rotated))
+            })
+            .min_by_key(|(index, ..)| {
+                self.waste_rects[*index].width * self.waste_rects[*index].height
+            });
+        if let Some((index, width, height, rotated)) = waste {
+            return Some((SkylineSlot::Waste(index, width, height), rotated));
+        }
+
+        orientations
+            .into_iter()
+            .filter_map(|(width, height, rotated)| {
+                self.find_skyline_position(width, height)
+                    .map(|(index, rect)| (index, rect, rotated))
+            })
+            .min_by_key(|(index, rect, _)| (rect.bottom(), self.skyline[*index].width))
+            .map(|(index, rect, rotated)| (SkylineSlot::Skyline(index, rect), rotated))
+    }
+}
+
+enum SkylineSlot {
+    // Index of the waste rectangle and the footprint
+    Waste(usize, u32, u32),
+    // Index of the skyline node and the footprint
+    Skyline(usize, Rect),
+}
+
+impl TexturePlacer for SkylineTexturePlacer {
+    fn config(&self) -> &TexturePlacerConfig {
+        &self.config
+    }
+
+    fn place_texture(
+        &mut self,
+        bounding_texture: ClusterBoundingTexture,
+        children: Vec<(PolygonID, ChildUVPolygon)>,
+        cluster_id: ClusterID,
+        parent_atlas_id: AtlasID,
+    ) -> (PlacedTextureGeometry, Vec<Option<PlacedUVPolygon>>) {
+        let (_, _, buffered_width, buffered_height) = bounding_texture.get_buffered_geometry();
+        let (scaled_width, scaled_height) = self.scale_dimensions(
+            buffered_width,
+            buffered_height,
+            bounding_texture.downsample_factor.value(),
+        );
+        let width = scaled_width + self.config.padding;
+        let height = scaled_height + self.config.padding;
+
+        let Some((slot, rotated)) = self.find_placement(width, height) else {
+            panic!("Texture could not be placed: {}", cluster_id);
+        };
+        let rect = match slot {
+            SkylineSlot::Waste(index, width, height) => {
+                self.place_in_waste_rect(index, width, height)
+            }
+            SkylineSlot::Skyline(index, rect) => {
+                self.add_skyline_level(index, rect);
+                rect
+            }
+        };
+
+        let bounding_placed = PlacedTextureGeometry {
+            cluster_id,
+            atlas_id: parent_atlas_id,
+            origin: (rect.x + self.config.padding, rect.y + self.config.padding),
+            width: rect.width - self.config.padding,
+            height: rect.height - self.config.padding,
+            rotated,
+            mask: None,
+        };
+        let children_placed = place_children(&self.config, &bounding_placed, &children);
+        (bounding_placed, children_placed)
+    }
+
+    fn can_place(&self, texture: &ClusterBoundingTexture) -> bool {
+        let (_, _, buffered_width, buffered_height) = texture.get_buffered_geometry();
+        let (scaled_width, scaled_height) = self.scale_dimensions(
+            buffered_width,
+            buffered_height,
+            texture.downsample_factor.value(),
+        );
+        self.find_placement(
+            scaled_width + self.config.padding,
+            scaled_height + self.config.padding,
+        )
+        .is_some()
+    }
+
+    fn reserve(&mut self, placed: &PlacedTextureGeometry) -> bool {
+        // The skyline is raised over the whole width of the placement, which also gives up the
+        // free space below it
+        let used = Rect::reserved(&self.config, placed);
+        let mut skyline = Vec::with_capacity(self.skyline.len() + 2);
+        for node in &self.skyline {
+            let node_right = node.x + node.width;
+            if node_right <= used.x || node.x >= used.right() || node.y >= used.bottom() {
+                skyline.push(*node);
+                continue;
+            }
+            if node.x < used.x {
+                skyline.push(SkylineNode {
+                    width: used.x - node.x,
+                    ..*node
+                });
+            }
+            let (left, right) = (node.x.max(used.x), node_right.min(used.right()));
+            skyline.push(SkylineNode {
+                x: left,
+                y: used.bottom(),
+                width: right - left,
+            });
+            if node_right > used.right() {
+                skyline.push(SkylineNode {
+                    x: used.right(),
+                    y: node.y,
+                    width: node_right - used.right(),
+                });
+            }
+        }
+        skyline.dedup_by(|node, previous| {
+            let merged = node.y == previous.y;
+            if merged {
+                previous.width += node.width;
+            }
+            merged
+        });
+        self.skyline = skyline;
+        self.waste_rects.retain(|rect| !rect.intersects(&used));
+        true
+    }
+
+    fn new_page(&self) -> Option<Self> {
+        Some(Self::new(self.config.clone(), self.use_waste_map))
+    }
+
+    fn reset_param(&mut self) {
+        self.skyline = vec![SkylineNode {
+            x: 0,
+            y: 0,
+            width: self.config.width,
+        }];
+        self.waste_rects.clear();
+    }
+}
+
+/// Rule used by the shelf placer to choose a shelf for a texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShelfHeuristic {
+    /// Only the last opened shelf is considered
+    #[default]
+    NextFit,
+    /// The first shelf with enough room is used
+    FirstFit,
+    /// The shelf whose height is closest to the texture height is used
+    BestHeightFit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    used_width: u32,
+}
+
+/// Shelf algorithm
+/// - places textures left to right in rows whose height is set by the first texture of the row
+/// - very little bookkeeping, suitable for draft conversions where speed matters more than occupancy
+#[derive(Clone)]
+pub struct ShelfTexturePlacer {
+    config: TexturePlacerConfig,
+    heuristic: ShelfHeuristic,
+    shelves: Vec<Shelf>,
+}
+
+impl ShelfTexturePlacer {
+    pub fn new(config: TexturePlacerConfig, heuristic: ShelfHeuristic) -> Self {
+        ShelfTexturePlacer {
+            config,
+            heuristic,
+            shelves: Vec::new(),
+        }
+    }
+
+    pub fn heuristic(&self) -> ShelfHeuristic {
+        self.heuristic
+    }
+
+    fn fits_shelf(&self, shelf: &Shelf, width: u32, height: u32) -> bool {
+        shelf.height >= height && shelf.used_width + width <= self.config.width
+    }
+
+    fn find_shelf(&self, width: u32, height: u32) -> Option<usize> {
+        match self.heuristic {
+            ShelfHeuristic::NextFit => self
+                .shelves
+                .len()
+                .checked_sub(1)
+                .filter(|&index| self.fits_shelf(&self.shelves[index], width, height)),
+            ShelfHeuristic::FirstFit => self
+                .shelves
+                .iter()
+                .position(|shelf| self.fits_shelf(shelf, width, height)),
+            ShelfHeuristic::BestHeightFit => self
+                .shelves
+                .iter()
+                .enumerate()
+                .filter(|(_, shelf)| self.fits_shelf(shelf, width, height))
+                .min_by_key(|(_, shelf)| shelf.height - height)
+                .map(|(index, _)| index),
+        }
+    }
+
+    fn next_shelf_y(&self) -> u32 {
+        self.shelves
+            .last()
+            .map(|shelf| shelf.y + shelf.height)
+            .unwrap_or(0)
+    }
+
+    fn can_open_shelf(&self, width: u32, height: u32) -> bool {
+        width <= self.config.width && self.next_shelf_y() + height <= self.config.height
+    }
+
+    // Existing shelves are tried first; a new shelf is opened with the lowest possible height
+    fn find_placement(&self, width: u32, height: u32) -> Option<(Option<usize>, u32, u32, bool)> {
+        let orientations = self.config.orientations(width, height);
+        orientations
+            .iter()
+            .find_map(|&(width, height, rotated)| {
+                self.find_shelf(width, height)
+                    .map(|index| (Some(index), width, height, rotated))
+            })
+            .or_else(|| {
+                orientations
+                    .into_iter()
+                    .filter(|&(width, height, _)| self.can_open_shelf(width, height))
+                    .min_by_key(|&(_, height, _)| height)
+                    .map(|(width, height, rotated)| (None, width, height, rotated))
+            })
+    }
+}
+
+impl TexturePlacer for ShelfTexturePlacer {
+    fn config(&self) -> &TexturePlacerConfig {
+        &self.config
+    }
+
+    fn place_texture(
+        &mut self,
+        bounding_texture: ClusterBoundingTexture,
+        children: Vec<(PolygonID, ChildUVPolygon)>,
+        cluster_id: ClusterID,
+        parent_atlas_id: AtlasID,
+    ) -> (PlacedTextureGeometry, Vec<Option<PlacedUVPolygon>>) {
+        let (_, _, buffered_width, buffered_height) = bounding_texture.get_buffered_geometry();
+        let (scaled_width, scaled_height) = self.scale_dimensions(
+            buffered_width,
+            buffered_height,
+            bounding_texture.downsample_factor.value(),
+        );
+        let width = scaled_width + self.config.padding;
+        let height = scaled_height + self.config.padding;
+
+        let Some((index, width, height, rotated)) = self.find_placement(width, height) else {
+            panic!("Texture could not be placed: {}", cluster_id);
+        };
+        let index = index.unwrap_or_else(|| {
+            self.shelves.push(Shelf {
+                y: self.next_shelf_y(),
+                height,
+                used_width: 0,
+            });
+            self.shelves.len() - 1
+        });
+
+        let shelf = &mut self.shelves[index];
+        let (x, y) = (shelf.used_width, shelf.y);
+        shelf.used_width += width;
+
+        let bounding_placed = PlacedTextureGeometry {
+            cluster_id,
+            atlas_id: parent_atlas_id,
+            origin: (x + self.config.padding, y + self.config.padding),
+            width: width - self.config.padding,
+            height: height - self.config.padding,
+            rotated,
+            mask: None,
+        };
+        let children_placed = place_children(&self.config, &bounding_placed, &children);
+        (bounding_placed, children_placed)
+    }
+
+    fn can_place(&self, texture: &ClusterBoundingTexture) -> bool {
+        let (_, _, buffered_width, buffered_height) = texture.get_buffered_geometry();
+        let (scaled_width, scaled_height) = self.scale_dimensions(
+            buffered_width,
+            buffered_height,
+            texture.downsample_factor.value(),
+        );
+        self.find_placement(
+            scaled_width + self.config.padding,
+            scaled_height + self.config.padding,
+        )
+        .is_some()
+    }
+
+    fn new_page(&self) -> Option<Self> {
+        Some(Self::new(self.config.clone(), self.heuristic))
+    }
+
+    fn reset_param(&mut self) {
+        self.shelves.clear();
+    }
+}
+
+// Liang-Barsky clipping of the segment against an axis-aligned rectangle
+fn segment_intersects_rect(
+    (x0, y0): (f64, f64),
+    (x1, y1): (f64, f64),
+    (min_x, min_y, max_x, max_y): (f64, f64, f64, f64),
+) -> bool {
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+    for (p, q) in [
+        (-dx, x0 - min_x),
+        (dx, max_x - x0),
+        (-dy, y0 - min_y),
+        (dy, max_y - y0),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+            if t0 > t1 {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Mark every cell that a polygon touches: the cells crossed by its edges and the cells inside it
+fn rasterize_polygons(polygons: &[Vec<(f64, f64)>], columns: u32, rows: u32) -> Vec<bool> {
+    let mut cells = vec![false; (columns * rows) as usize];
+    let cell_range = |min: f64, max: f64, count: u32| {
+        let first = (min.floor().max(0.0) as u32).min(count.saturating_sub(1));
+        let last = (max.floor().max(0.0) as u32).min(count.saturating_sub(1));
+        first..=last
+    };
+
+    for polygon in polygons.iter().filter(|polygon| !polygon.is_empty()) {
+        for (i, &start) in polygon.iter().enumerate() {
+            let end = polygon[(i + 1) % polygon.len()];
+            for row in cell_range(start.1.min(end.1), start.1.max(end.1), rows) {
+                for column in cell_range(start.0.min(end.0), start.0.max(end.0), columns) {
+                    let cell = (
+                        column as f64,
+                        row as f64,
+                        column as f64 + 1.0,
+                        row as f64 + 1.0,
+                    );
+                    if segment_intersects_rect(start, end, cell) {
+                        cells[(row * columns + column) as usize] = true;
+                    }
+                }
+            }
+        }
+
+        let (min_x, min_y, max_x, max_y) = polygon.iter().fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(min_x, min_y, max_x, max_y), &(x, y)| {
+                (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+            },
+        );
+        for row in cell_range(min_y, max_y, rows) {
+            for column in cell_range(min_x, max_x, columns) {
+                let center = (column as f64 + 0.5, row as f64 + 0.5);
+                if is_point_inside_polygon(center, polygon) {
+                    cells[(row * columns + column) as usize] = true;
+                }
+            }
+        }
+    }
+    cells
+}
+
+// Grow the marked cells by `radius` cells in every direction
+fn dilate_cells(cells: &[bool], columns: u32, rows: u32, radius: u32) -> Vec<bool> {
+    if radius == 0 {
+        return cells.to_vec();
+    }
+    let mut dilated = vec![false; cells.len()];
+    for row in 0..rows {
+        for column in 0..columns {
+            if !cells[(row * columns + column) as usize] {
+                continue;
+            }
+            for y in row.saturating_sub(radius)..(row + radius + 1).min(rows) {
+                for x in column.saturating_sub(radius)..(column + radius + 1).min(columns) {
+                    dilated[(y * columns + x) as usize] = true;
+                }
+            }
+        }
+    }
+    dilated
+}
+
+// Occupied cells of a texture, relative to the cell of its origin
+#[derive(Debug, Clone)]
+struct CellShape {
+    // Size of the texture on the atlas in pixels
+    width: u32,
+    height: u32,
+    rotated: bool,
+    mask: PlacementMask,
+    // Mask cells plus padding as runs of (row, first column, end column); may reach outside the
+    // texture
+    footprint: Vec<(i32, i32, i32)>,
+}
+
+/// Nesting algorithm
+/// - packs the clusters by the outlines of their polygons on a coarse grid of `cell_size` pixels,
+///   so that the empty corners of a bounding box can hold other clusters
+/// - bounding boxes may overlap on the atlas; the exporters only write the masked cells
+/// - slower than the rectangle placers, since every grid position is tested (one lookup per row
+///   of the texture)
+#[derive(Clone)]
+pub struct NestingTexturePlacer {
+    config: TexturePlacerConfig,
+    cell_size: u32,
+    columns: u32,
+    rows: u32,
+    occupied: Vec<bool>,
+    // Number of occupied cells before each column of each row (`columns + 1` per row), so that a
+    // run of cells is checked at once
+    occupied_before: Vec<u32>,
+    // Rows above this one are full
+    first_free_row: u32,
+}
+
+impl NestingTexturePlacer {
+    pub fn new(config: TexturePlacerConfig, cell_size: u32) -> Self {
+        let cell_size = cell_size.max(1);
+        let columns = config.width.div_ceil(cell_size);
+        let rows = config.height.div_ceil(cell_size);
+        NestingTexturePlacer {
+            config,
+            cell_size,
+            columns,
+            rows,
+            occupied: vec![false; (columns * rows) as usize],
+            occupied_before: vec![0; ((columns + 1) * rows) as usize],
+            first_free_row: 0,
+        }
+    }
+
+    pub fn cell_size(&self) -> u32 {
+        self.cell_size
+    }
+
+    fn shapes(
+        &self,
+        texture: &ClusterBoundingTexture,
+        children: Option<&[(PolygonID, ChildUVPolygon)]>,
+    ) -> Vec<CellShape> {
+        let (width, height) = texture.get_scaled_size();
+        self.config
+            .orientations(width, height)
+            .into_iter()
+            .map(|(width, height, rotated)| self.shape(width, height, rotated, children))
+            .collect()
+    }
+
+    fn shape(
+        &self,
+        width: u32,
+        height: u32,
+        rotated: bool,
+        children: Option<&[(PolygonID, ChildUVPolygon)]>,
+    ) -> CellShape {
+        let cell_size = self.cell_size as f64;
+        let columns = width.div_ceil(self.cell_size);
+        let rows = height.div_ceil(self.cell_size);
+
+        let cells = match children {
+            Some(children) => {
+                // Polygon outlines in cell units, after rotation
+                let polygons = children
+                    .iter()
+                    .map(|(_, child)| {
+                        child
+                            .cropped_uv_coords
+                            .iter()
+                            .map(|&(u, v)| {
+                                let (x, y) = if rotated {
+                                    (width as f64 - (1.0 - v) * width as f64, u * height as f64)
+                                } else {
+                                    (u * width as f64, (1.0 - v) * height as f64)
+                                };
+                                (x / cell_size, y / cell_size)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                // One more cell around the polygons keeps the texels used for filtering
+                let cells = rasterize_polygons(&polygons, columns, rows);
+                dilate_cells(&cells, columns, rows, 1)
+            }
+            None => vec![true; (columns * rows) as usize],
+        };
+
+        CellShape {
+            width,
+            height,
+            rotated,
+            footprint: self.footprint(&cells, columns, rows),
+            mask: PlacementMask {
+                cell_size: self.cell_size,
+                columns,
+                rows,
+                cells,
+            },
+        }
+    }
+
+    // Runs of the given cells plus padding
+    fn footprint(&self, cells: &[bool], columns: u32, rows: u32) -> Vec<(i32, i32, i32)> {
+        let padding_cells = self.config.padding.div_ceil(self.cell_size) as i32;
+        let mut footprint = HashSet::new();
+        for row in 0..rows as i32 {
+            for column in 0..columns as i32 {
+                if !cells[(row * columns as i32 + column) as usize] {
+                    continue;
+                }
+                for dy in -padding_cells..=padding_cells {
+                    for dx in -padding_cells..=padding_cells {
+                        footprint.insert((column + dx, row + dy));
+                    }
+                }
+            }
+        }
+        let mut cells_in_footprint = footprint.into_iter().collect::<Vec<_>>();
+        cells_in_footprint.sort_unstable_by_key(|&(x, y)| (y, x));
+        let mut footprint: Vec<(i32, i32, i32)> = Vec::new();
+        for (x, y) in cells_in_footprint {
+            match footprint.last_mut() {
+                Some((row, _, end)) if *row == y && *end == x => *end += 1,
+                _ => footprint.push((y, x, x + 1)),
+            }
+        }
+        footprint
+    }
+
+    fn fits_at(&self, shape: &CellShape, column: u32, row: u32) -> bool {
+        if column * self.cell_size + shape.width > self.config.width
+            || row * self.cell_size + shape.height > self.config.height
+        {
+            return false;
+        }
+        shape.footprint.iter().all(|&run| {
+            // Padding outside the page does not matter
+            self.clip_run(run, column, row)
+                .is_none_or(|(y, start, end)| self.occupied_in_run(y, start, end) == 0)
+        })
+    }
+
+    // A footprint run at the given position, limited to the page
+    fn clip_run(&self, run: (i32, i32, i32), column: u32, row: u32) -> Option<(u32, u32, u32)> {
+        let (dy, start, end) = run;
+        let y = row as i32 + dy;
+        let start = (column as i32 + start).clamp(0, self.columns as i32) as u32;
+        let end = (column as i32 + end).clamp(0, self.columns as i32) as u32;
+        (y >= 0 && y < self.rows as i32 && start < end).then_some((y as u32, start, end))
+    }
+
+    fn occupied_in_run(&self, row: u32, start: u32, end: u32) -> u32 {
+        let offset = (row * (self.columns + 1)) as usize;
+        self.occupied_before[offset + end as usize] - self.occupied_before[offset + start as usize]
+    }
+
+    // The topmost, then leftmost position of any of the shapes
+    fn find_position(&self, shapes: Vec<CellShape>) -> Option<(CellShape, u32, u32)> {
+        shapes
+            .into_iter()
+            .filter_map(|shape| {
+                (self.first_free_row..self.rows)
+                    .flat_map(|row| (0..self.columns).map(move |column| (column, row)))
+                    .find(|&(column, row)| self.fits_at(&shape, column, row))
+                    .map(|(column, row)| (shape, column, row))
+            })
+            .min_by_key(|(_, column, row)| (*row, *column))
+    }
+
+    fn occupy(&mut self, footprint: &[(i32, i32, i32)], column: u32, row: u32) {
+        for &run in footprint {
+            let Some((y, start, end)) = self.clip_run(run, column, row) else {
+                continue;
+            };
+            let cells = (y * self.columns) as usize;
+            self.occupied[cells + start as usize..cells + end as usize].fill(true);
+
+            // Recount the row from the start of the run
+            let counts = (y * (self.columns + 1)) as usize;
+            for x in start..self.columns {
+                self.occupied_before[counts + x as usize + 1] = self.occupied_before
+                    [counts + x as usize]
+                    + self.occupied[cells + x as usize] as u32;
+            }
+        }
+        while self.first_free_row < self.rows
+            && self.occupied_in_run(self.first_free_row, 0, self.columns) == self.columns
+        {
+            self.first_free_row += 1;
+        }
+    }
+}
+
+impl TexturePlacer for NestingTexturePlacer {
+    fn config(&self) -> &TexturePlacerConfig {
+        &self.config
+    }
+
+    fn place_texture(
+        &mut self,
+        bounding_texture: ClusterBoundingTexture,
+        children: Vec<(PolygonID, ChildUVPolygon)>,
+        cluster_id: ClusterID,
+        parent_atlas_id: AtlasID,
+    ) -> (PlacedTextureGeometry, Vec<Option<PlacedUVPolygon>>) {
+        let shapes = self.shapes(&bounding_texture, Some(&children));
+        let Some((shape, column, row)) = self.find_position(shapes) else {
+            panic!("Texture could not be placed: {}", cluster_id);
+        };
+        self.occupy(&shape.footprint, column, row);
+
+        let bounding_placed = PlacedTextureGeometry {
+            cluster_id,
+            atlas_id: parent_atlas_id,
+            origin: (column * self.cell_size, row * self.cell_size),
+            width: shape.width,
+            height: shape.height,
+            rotated: shape.rotated,
+            mask: Some(shape.mask),
+        };
+        let children_placed = place_children(&self.config, &bounding_placed, &children);
+        (bounding_placed, children_placed)
+    }
+
+    fn can_place(&self, texture: &ClusterBoundingTexture) -> bool {
+        self.find_position(self.shapes(texture, None)).is_some()
+    }
+
+    fn can_place_cluster(
+        &self,
+        texture: &ClusterBoundingTexture,
+        children: &[(PolygonID, ChildUVPolygon)],
+    ) -> bool {
+        self.find_position(self.shapes(texture, Some(children)))
+            .is_some()
+    }
+
+    fn reserve(&mut self, placed: &PlacedTextureGeometry) -> bool {
+        let cell_size = self.cell_size;
+        let (column, row) = (placed.origin.0 / cell_size, placed.origin.1 / cell_size);
+        let footprint = match &placed.mask {
+            // Placements on the same grid keep their outline
+            Some(mask)
+                if mask.cell_size == cell_size
+                    && placed.origin.0.is_multiple_of(cell_size)
+                    && placed.origin.1.is_multiple_of(cell_size) =>
+            {
+                self.footprint(&mask.cells, mask.columns, mask.rows)
+            }
+            _ => {
+                let columns = (placed.origin.0 + placed.width).div_ceil(cell_size) - column;
+                let rows = (placed.origin.1 + placed.height).div_ceil(cell_size) - row;
+                self.footprint(&vec![true; (columns * rows) as usize], columns, rows)
+            }
+        };
+        self.occupy(&footprint, column, row);
+        true
+    }
+
+    fn new_page(&self) -> Option<Self> {
+        Some(Self::new(self.config.clone(), self.cell_size))
+    }
+
+    fn reset_param(&mut self) {
+        self.occupied.fill(false);
+        self.occupied_before.fill(0);
+        self.first_free_row = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::texture::{DownsampleFactor, PolygonMappedTexture};
+
+    // A rectangular cluster of `width` x `height` pixels (including the buffer) cut from a 1024x1024 image
+    fn rect_texture(
+        width: u32,
+        height: u32,
+    ) -> (ClusterBoundingTexture, Vec<(PolygonID, ChildUVPolygon)>) {
+        let buffer = 1;
+        let max_u = (width - buffer * 2) as f64 / 1024.0;
+        let max_v = (height - buffer * 2) as f64 / 1024.0;
+        let texture = PolygonMappedTexture::new(
+            Path::new("dummy.png"),
+            (1024, 1024),
+            &[
+                (0.0, 1.0),
+                (max_u, 1.0),
+                (max_u, 1.0 - max_v),
+                (0.0, 1.0 - max_v),
+            ],
+            DownsampleFactor::new(&1.0),
+        );
+        let bounding_texture = ClusterBoundingTexture::new(&texture, buffer);
+        let child = bounding_texture.get_child(&texture);
+        (bounding_texture, vec![("polygon".to_string(), child)])
+    }
+
+    fn square_texture(size: u32) -> (ClusterBoundingTexture, Vec<(PolygonID, ChildUVPolygon)>) {
+        rect_texture(size, size)
+    }
+
+    fn assert_no_overlap(placed: &[PlacedTextureGeometry], config: &TexturePlacerConfig) {
+        for (i, a) in placed.iter().enumerate() {
+            assert!(a.origin.0 + a.width <= config.width);
+            assert!(a.origin.1 + a.height <= config.height);
+            for b in placed.iter().skip(i + 1) {
+                let separated = a.origin.0 + a.width <= b.origin.0
+                    || b.origin.0 + b.width <= a.origin.0
+                    || a.origin.1 + a.height <= b.origin.1
+                    || b.origin.1 + b.height <= a.origin.1;
+                assert!(separated, "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_rects_fills_atlas() {
+        let heuristics = [
+            MaxRectsHeuristic::BestShortSideFit,
+            MaxRectsHeuristic::BestLongSideFit,
+            MaxRectsHeuristic::BestAreaFit,
+            MaxRectsHeuristic::BottomLeft,
+            MaxRectsHeuristic::ContactPoint,
+        ];
+        for heuristic in heuristics {
+            let config = TexturePlacerConfig::new(64, 64, 0);
+            let mut placer = MaxRectsTexturePlacer::new(config.clone(), heuristic);
+            let mut placed = Vec::new();
+            // 16 squares of 16x16 exactly fill the 64x64 atlas
+            for i in 0..16 {
+                let (texture, children) = square_texture(16);
+                assert!(placer.can_place(&texture), "{:?}: {}", heuristic, i);
+                let (geometry, _) = placer.place_texture(texture, children, i.to_string(), 0);
+                placed.push(geometry);
+            }
+            assert!(!placer.can_place(&square_texture(4).0));
+            assert_no_overlap(&placed, &config);
+
+            placer.reset_param();
+            assert!(placer.can_place(&square_texture(64).0));
+        }
+    }
+
+    #[test]
+    fn test_max_rects_uv_matches_guillotine() {
+        let config = TexturePlacerConfig::new(64, 64, 2);
+        let (texture, children) = square_texture(20);
+
+        let mut guillotine = GuillotineTexturePlacer::new(config.clone());
+        let mut max_rects = MaxRectsTexturePlacer::new(config, MaxRectsHeuristic::default());
+        let (geometry_0, uv_0) =
+            guillotine.place_texture(texture.clone(), children.clone(), "0".to_string(), 0);
+        let (geometry_1, uv_1) = max_rects.place_texture(texture, children, "0".to_string(), 0);
+
+        assert_eq!(geometry_0.origin, geometry_1.origin);
+        assert_eq!(
+            uv_0[0].as_ref().unwrap().placed_uv_coords,
+            uv_1[0].as_ref().unwrap().placed_uv_coords
+        );
+    }
+
+    #[test]
+    fn test_skyline_fills_atlas() {
+        for use_waste_map in [false, true] {
+            let config = TexturePlacerConfig::new(64, 64, 0);
+            let mut placer = SkylineTexturePlacer::new(config.clone(), use_waste_map);
+            let mut placed = Vec::new();
+            for i in 0..16 {
+                let (texture, children) = square_texture(16);
+                assert!(placer.can_place(&texture));
+                let (geometry, _) = placer.place_texture(texture, children, i.to_string(), 0);
+                placed.push(geometry);
+            }
+            assert!(!placer.can_place(&square_texture(4).0));
+            assert_no_overlap(&placed, &config);
+        }
+    }
+
+    #[test]
+    fn test_skyline_reuses_waste() {
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let mut placer = SkylineTexturePlacer::new(config.clone(), true);
+        let mut placed = Vec::new();
+        // A short texture followed by a wide one leaves a gap under the wide one
+        for (i, size) in [8, 32, 32, 8].into_iter().enumerate() {
+            let (texture, children) = square_texture(size);
+            let (geometry, _) = placer.place_texture(texture, children, i.to_string(), 0);
+            placed.push(geometry);
+        }
+        assert_no_overlap(&placed, &config);
+        // The last 8x8 texture must fill the gap next to the first one
+        assert_eq!(placed[3].origin, (0, 8));
+    }
+
+    #[test]
+    fn test_skyline_many_textures() {
+        use rand::{Rng, SeedableRng};
+
+        // Lowest start of a texture at every node, scanning the nodes under it one by one
+        fn brute_force_position(
+            placer: &SkylineTexturePlacer,
+            width: u32,
+            height: u32,
+        ) -> Option<(u32, u32)> {
+            (0..placer.skyline.len())
+                .filter_map(|index| {
+                    let x = placer.skyline[index].x;
+                    let y = placer.skyline[index..]
+                        .iter()
+                        .take_while(|node| node.x < x + width)
+                        .map(|node| node.y)
+                        .max()?;
+                    (x + width <= placer.config.width && y + height <= placer.config.height)
+                        .then_some((y + height, placer.skyline[index].width, x, y))
+                })
+                .min()
+                .map(|(_, _, x, y)| (x, y))
+        }
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let config = TexturePlacerConfig::new(1024, 1024, 1);
+        let mut placer = SkylineTexturePlacer::new(config.clone(), true);
+        let mut placed = Vec::new();
+        for i in 0..2000 {
+            let (texture, children) = rect_texture(rng.gen_range(3..40), rng.gen_range(3..40));
+            if !placer.can_place(&texture) {
+                break;
+            }
+            let (_, _, width, height) = texture.get_buffered_geometry();
+            let expected = brute_force_position(&placer, width + 1, height + 1);
+            let found = placer
+                .find_skyline_position(width + 1, height + 1)
+                .map(|(_, rect)| (rect.x, rect.y));
+            assert_eq!(found, expected);
+
+            let (geometry, _) = placer.place_texture(texture, children, i.to_string(), 0);
+            placed.push(geometry);
+            assert!(placer.waste_rects.len() <= MAX_WASTE_RECTS);
+            assert!(placer
+                .skyline
+                .windows(2)
+                .all(|nodes| nodes[0].y != nodes[1].y));
+        }
+        assert!(placed.len() > 1000);
+        assert_no_overlap(&placed, &config);
+    }
+
+    #[test]
+    fn test_shelf_fills_atlas() {
+        let heuristics = [
+            ShelfHeuristic::NextFit,
+            ShelfHeuristic::FirstFit,
+            ShelfHeuristic::BestHeightFit,
+        ];
+        for heuristic in heuristics {
+            let config = TexturePlacerConfig::new(64, 64, 0);
+            let mut placer = ShelfTexturePlacer::new(config.clone(), heuristic);
+            let mut placed = Vec::new();
+            for i in 0..16 {
+                let (texture, children) = square_texture(16);
+                assert!(placer.can_place(&texture));
+                let (geometry, _) = placer.place_texture(texture, children, i.to_string(), 0);
+                placed.push(geometry);
+            }
+            assert!(!placer.can_place(&square_texture(4).0));
+            assert_no_overlap(&placed, &config);
+        }
+    }
+
+    #[test]
+    fn test_shelf_heuristics() {
+        let place = |heuristic, sizes: &[(u32, u32)]| {
+            let config = TexturePlacerConfig::new(64, 64, 0);
+            let mut placer = ShelfTexturePlacer::new(config.clone(), heuristic);
+            let mut placed = Vec::new();
+            for (i, &(width, height)) in sizes.iter().enumerate() {
+                let (texture, children) = rect_texture(width, height);
+                let (geometry, _) = placer.place_texture(texture, children, i.to_string(), 0);
+                placed.push(geometry);
+            }
+            assert_no_overlap(&placed, &config);
+            placed[2].origin
+        };
+
+        // A 16px shelf and a 32px shelf are opened, then an 8px texture is placed
+        let sizes = [(16, 16), (32, 32), (8, 8)];
+        assert_eq!(place(ShelfHeuristic::NextFit, &sizes), (32, 16));
+        assert_eq!(place(ShelfHeuristic::FirstFit, &sizes), (16, 0));
+        assert_eq!(place(ShelfHeuristic::BestHeightFit, &sizes), (16, 0));
+
+        // A 32px shelf and a 16px shelf are opened; only best-height-fit picks the lower one
+        let sizes = [(32, 32), (48, 16), (8, 8)];
+        assert_eq!(place(ShelfHeuristic::NextFit, &sizes), (48, 32));
+        assert_eq!(place(ShelfHeuristic::FirstFit, &sizes), (32, 0));
+        assert_eq!(place(ShelfHeuristic::BestHeightFit, &sizes), (48, 32));
+    }
+
+    #[test]
+    fn test_into_box_keeps_boxed_placer() {
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let boxed = GuillotineTexturePlacer::new(config).into_box();
+        let address = boxed.as_ref() as *const dyn DynTexturePlacer as *const u8;
+        let reboxed = boxed.into_box();
+        assert_eq!(
+            reboxed.as_ref() as *const dyn DynTexturePlacer as *const u8,
+            address
+        );
+    }
+
+    #[test]
+    fn test_reserve() {
+        let config = TexturePlacerConfig::new(64, 64, 0);
+        let reserved = [
+            PlacedTextureGeometry::new("a".to_string(), 0, (8, 8), 24, 24),
+            PlacedTextureGeometry::new("b".to_string(), 0, (40, 0), 16, 40),
+        ];
+        let mut placers = all_placers(&config);
+        placers.push(Box::new(NestingTexturePlacer::new(config.clone(), 4)));
+        let mut unsupported = 0;
+        for mut placer in placers {
+            if !reserved.iter().all(|placed| placer.reserve(placed)) {
+                unsupported += 1;
+                continue;
+            }
+            let mut placed = reserved.to_vec();
+            loop {
+                let (texture, children) = square_texture(8);
+                if !placer.can_place(&texture) {
+                    break;
+                }
+                let (geometry, _) =
+                    placer.place_texture(texture, children, placed.len().to_string(), 0);
+                placed.push(geometry);
+            }
+            assert!(placed.len() > reserved.len() + 10);
+            assert_no_overlap(&placed, &config);
+        }
+        // Only the shelf placer cannot reserve arbitrary areas
+        assert_eq!(unsupported, 1);
+    }
+
+    fn all_placers(config: &TexturePlacerConfig) -> Vec<Box<dyn TexturePlacer>> {
+        vec![
+            Box::new(GuillotineTexturePlacer::new(config.clone())),
+            Box::new(MaxRectsTexturePlacer::new(
+                config.clone(),
+                MaxRectsHeuristic::default(),
+            )),
+            Box::new(SkylineTexturePlacer::new(config.clone(), true)),
+            Box::new(ShelfTexturePlacer::new(
+                config.clone(),
+                ShelfHeuristic::default(),
+            )),
+        ]
+    }
+
+    #[test]
+    fn test_rotation() {
+        // A 16x48 texture only fits a 64x16 atlas when rotated
+        let config = TexturePlacerConfig::new(64, 16, 0);
+        for placer in all_placers(&config) {
+            assert!(!placer.can_place(&rect_texture(16, 48).0));
+        }
+
+        for mut placer in all_placers(&config.with_rotation(true)) {
+            let (texture, children) = rect_texture(16, 48);
+            assert!(placer.can_place(&texture));
+            let (geometry, uv) = placer.place_texture(texture, children, "0".to_string(), 0);
+            assert!(geometry.rotated);
+            assert_eq!((geometry.width, geometry.height), (48, 16));
+
+            // The top-left vertex at (1, 1) moves to (48 - 1, 1)
+            let uv = &uv[0].as_ref().unwrap().placed_uv_coords;
+            assert_eq!(uv[0], (47.0 / 64.0, 1.0 - 1.0 / 16.0));
+        }
+    }
+
+    #[test]
+    fn test_size_rounding() {
+        let config = TexturePlacerConfig::new(3000, 3000, 0);
+        assert_eq!((config.width(), config.height()), (4096, 4096));
+
+        let config = TexturePlacerConfig::new_with_rounding(3000, 1000, 0, SizeRounding::Exact);
+        assert_eq!((config.width(), config.height()), (3000, 1000));
+        assert_eq!(config.fit_page_size(123, 45), (123, 45));
+
+        let config =
+            TexturePlacerConfig::new_with_rounding(3001, 1000, 0, SizeRounding::MultipleOf(4));
+        assert_eq!((config.width(), config.height()), (3004, 1000));
+        assert_eq!(config.fit_page_size(123, 45), (124, 48));
+    }
+
+    #[test]
+    fn test_non_power_of_two_page() {
+        // Six 16px squares exactly fill a 48x32 page
+        let config = TexturePlacerConfig::new_with_rounding(48, 32, 0, SizeRounding::Exact);
+        for mut placer in all_placers(&config) {
+            let mut placed = Vec::new();
+            for i in 0..6 {
+                let (texture, children) = square_texture(16);
+                assert!(placer.can_place(&texture));
+                let (geometry, _) = placer.place_texture(texture, children, i.to_string(), 0);
+                placed.push(geometry);
+            }
+            assert!(!placer.can_place(&square_texture(4).0));
+            assert_no_overlap(&placed, &config);
+        }
+    }
+
+    // A right triangle with `size` pixel legs; lower-left half of its box, or upper-right half
+    fn triangle_texture(
+        size: u32,
+        lower_left: bool,
+    ) -> (ClusterBoundingTexture, Vec<(PolygonID, ChildUVPolygon)>) {
+        let max = size as f64 / 1024.0;
+        let corner = if lower_left {
+            (0.0, 1.0 - max)
+        } else {
+            (max, 1.0)
+        };
+        let texture = PolygonMappedTexture::new(
+            Path::new("dummy.png"),
+            (1024, 1024),
+            &[(0.0, 1.0), corner, (max, 1.0 - max)],
+            DownsampleFactor::new(&1.0),
+        );
+        let bounding_texture = ClusterBoundingTexture::new(&texture, 2);
+        let child = bounding_texture.get_child(&texture);
+        (bounding_texture, vec![("polygon".to_string(), child)])
+    }
+
+    // The masks of the placed textures must not share a pixel
+    fn assert_masks_disjoint(placed: &[PlacedTextureGeometry], config: &TexturePlacerConfig) {
+        for y in 0..config.height {
+            for x in 0..config.width {
+                let covering = placed
+                    .iter()
+                    .filter(|placed| {
+                        x >= placed.origin.0
+                            && y >= placed.origin.1
+                            && x < placed.origin.0 + placed.width
+                            && y < placed.origin.1 + placed.height
+                            && placed
+                                .mask
+                                .as_ref()
+                                .unwrap()
+                                .contains(x - placed.origin.0, y - placed.origin.1)
+                    })
+                    .count();
+                assert!(covering <= 1, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nesting() {
+        // Two 64px triangles only fit a 96x64 page when their boxes overlap
+        let config = TexturePlacerConfig::new_with_rounding(96, 64, 0, SizeRounding::Exact);
+        let mut guillotine = GuillotineTexturePlacer::new(config.clone());
+        let (texture, children) = triangle_texture(60, true);
+        guillotine.place_texture(texture, children, "0".to_string(), 0);
+        assert!(!guillotine.can_place(&triangle_texture(60, false).0));
+
+        let mut placer = NestingTexturePlacer::new(config.clone(), 4);
+        let mut placed = Vec::new();
+        for (i, lower_left) in [true, false].into_iter().enumerate() {
+            let (texture, children) = triangle_texture(60, lower_left);
+            assert!(placer.can_place_cluster(&texture, &children));
+            let (geometry, uv) = placer.place_texture(texture, children, i.to_string(), 0);
+            assert!(uv[0].is_some());
+            placed.push(geometry);
+        }
+        assert_eq!(placed[0].origin, (0, 0));
+        assert_masks_disjoint(&placed, &config);
+
+        // The corners shared by both triangles are inside the masks
+        for placed in &placed {
+            assert!(placed.mask.as_ref().unwrap().contains(0, 0));
+            assert!(placed.mask.as_ref().unwrap().contains(63, 63));
+        }
+    }
+
+    #[test]
+    fn test_nesting_many_textures() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let config = TexturePlacerConfig::new(256, 256, 2);
+        let mut placer = NestingTexturePlacer::new(config.clone(), 4);
+        let mut placed = Vec::new();
+        for i in 0..200 {
+            let (texture, children) = triangle_texture(rng.gen_range(8..48), rng.gen_bool(0.5));
+            if !placer.can_place_cluster(&texture, &children) {
+                continue;
+            }
+            let (geometry, _) = placer.place_texture(texture, children, i.to_string(), 0);
+            placed.push(geometry);
+        }
+        assert!(placed.len() > 20);
+        assert_masks_disjoint(&placed, &config);
+
+        // The row counts match the occupied cells
+        for row in 0..placer.rows {
+            for column in 0..placer.columns {
+                let count = (0..column)
+                    .filter(|x| placer.occupied[(row * placer.columns + x) as usize])
+                    .count() as u32;
+                assert_eq!(placer.occupied_in_run(row, 0, column), count);
+            }
+        }
+    }
+}
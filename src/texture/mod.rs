@@ -1,10 +1,10 @@
 use std::path::{Path, PathBuf};
 
 use image::{DynamicImage, GenericImageView, ImageBuffer};
-use utils::{calc_bbox, uv_to_pixel_coords};
+use utils::{calc_bbox, polygon_distance, uv_to_pixel_coords};
 
 pub mod cache;
-mod utils;
+pub(crate) mod utils;
 
 #[derive(Debug, Clone)]
 pub struct DownsampleFactor(f32);
@@ -23,12 +23,26 @@ impl DownsampleFactor {
     }
 }
 
+/// World-space locality of a polygon, used by `ClusterSortStrategy::Locality`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LocationHint {
+    /// World-space position (x, y), e.g. the projected centroid of the building
+    Position(f64, f64),
+    /// Precomputed key such as a tile index; close keys are placed together
+    TileKey(u64),
+}
+
 /// Texture image mapped to a polygon
 #[derive(Debug, Clone)]
 pub struct PolygonMappedTexture {
     // texture
     pub image_path: PathBuf,
     pub downsample_factor: DownsampleFactor,
+    // Weight used when a texel budget reduces the textures (higher keeps more texels)
+    pub priority: f32,
+    // Where the polygon is in the world, to keep nearby textures on the same page
+    pub location: Option<LocationHint>,
     // polygon
     pub pixel_coords: Vec<(u32, u32)>,
 }
@@ -45,10 +59,26 @@ impl PolygonMappedTexture {
         PolygonMappedTexture {
             image_path: image_path.to_path_buf(),
             downsample_factor,
+            priority: 1.0,
+            location: None,
             pixel_coords,
         }
     }
 
+    pub fn with_location(mut self, location: LocationHint) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: f32) -> Self {
+        if priority > 0.0 {
+            self.priority = priority;
+            self
+        } else {
+            panic!("The priority must be greater than 0.")
+        }
+    }
+
     #[inline]
     pub fn bbox(&self) -> (u32, u32, u32, u32) {
         calc_bbox(&self.pixel_coords)
@@ -66,6 +96,22 @@ impl PolygonMappedTexture {
         !(max_x_0 < min_x_1 || max_x_1 < min_x_0 || max_y_0 < min_y_1 || max_y_1 < min_y_0)
     }
 
+    /// Whether the polygons themselves come within `margin` pixels of each other
+    pub fn polygon_overlaps(&self, other: &Self, margin: u32) -> bool {
+        if self.image_path != other.image_path {
+            return false;
+        }
+
+        let ring = |texture: &Self| {
+            texture
+                .pixel_coords
+                .iter()
+                .map(|&(x, y)| (x as f64, y as f64))
+                .collect::<Vec<_>>()
+        };
+        polygon_distance(&ring(self), &ring(other)) <= margin as f64
+    }
+
     pub fn get_cropped_uv_coords(
         &self,
         x: u32,
@@ -74,18 +120,30 @@ impl PolygonMappedTexture {
         height: u32,
         buffer: u32,
     ) -> Vec<(f64, f64)> {
-        self.pixel_coords
-            .iter()
-            .map(|(px, py)| {
-                (
-                    (*px - x + buffer) as f64 / (width + buffer * 2) as f64,
-                    1.0 - (*py - y + buffer) as f64 / (height + buffer * 2) as f64,
-                )
-            })
-            .collect()
+        cropped_uv_coords(&self.pixel_coords, x, y, width, height, buffer)
     }
 }
 
+// UV coordinates of pixel coordinates on a crop of the source image with its buffer
+fn cropped_uv_coords(
+    pixel_coords: &[(u32, u32)],
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    buffer: u32,
+) -> Vec<(f64, f64)> {
+    pixel_coords
+        .iter()
+        .map(|(px, py)| {
+            (
+                (*px - x + buffer) as f64 / (width + buffer * 2) as f64,
+                1.0 - (*py - y + buffer) as f64 / (height + buffer * 2) as f64,
+            )
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct ClusterBoundingTexture {
     pub image_path: PathBuf,
@@ -110,6 +168,24 @@ impl ClusterBoundingTexture {
         }
     }
 
+    // Texture of a crop rectangle (x, y, width, height), as stored in a pack manifest
+    pub fn from_crop_rect(
+        image_path: &Path,
+        crop_rect: (u32, u32, u32, u32),
+        buffer: u32,
+        downsample_factor: DownsampleFactor,
+    ) -> Self {
+        let (x, y, width, height) = crop_rect;
+        Self {
+            image_path: image_path.to_path_buf(),
+            buffer,
+            crop_origin: (x, y),
+            crop_width: width,
+            crop_height: height,
+            downsample_factor,
+        }
+    }
+
     pub fn expand(&self, texture: &PolygonMappedTexture) -> Option<Self> {
         if self.image_path != texture.image_path {
             return None;
@@ -153,6 +229,65 @@ impl ClusterBoundingTexture {
         ChildUVPolygon { cropped_uv_coords }
     }
 
+    /// Smallest texture that still holds the given children (at least one), and the children
+    /// mapped onto it
+    pub fn fit_children(&self, children: &[ChildUVPolygon]) -> (Self, Vec<ChildUVPolygon>) {
+        let (buffered_x, buffered_y, buffered_width, buffered_height) =
+            self.get_buffered_geometry();
+        let pixel_coords = children
+            .iter()
+            .map(|child| {
+                child
+                    .cropped_uv_coords
+                    .iter()
+                    .map(|(u, v)| {
+                        (
+                            (buffered_x + (u * buffered_width as f64).round() as i32) as u32,
+                            (buffered_y + ((1.0 - v) * buffered_height as f64).round() as i32)
+                                as u32,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let (min_x, min_y, max_x, max_y) = pixel_coords
+            .iter()
+            .map(|coords| calc_bbox(coords))
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+            .unwrap();
+        let bounding_texture = Self {
+            crop_origin: (min_x, min_y),
+            crop_width: max_x - min_x,
+            crop_height: max_y - min_y,
+            ..self.clone()
+        };
+        let children = pixel_coords
+            .iter()
+            .map(|coords| ChildUVPolygon {
+                cropped_uv_coords: cropped_uv_coords(
+                    coords,
+                    min_x,
+                    min_y,
+                    max_x - min_x,
+                    max_y - min_y,
+                    self.buffer,
+                ),
+            })
+            .collect();
+        (bounding_texture, children)
+    }
+
+    // (x, y, width, height) of the crop in the source image, without the buffer
+    pub fn get_crop_rect(&self) -> (u32, u32, u32, u32) {
+        (
+            self.crop_origin.0,
+            self.crop_origin.1,
+            self.crop_width,
+            self.crop_height,
+        )
+    }
+
     pub fn get_buffered_geometry(&self) -> (i32, i32, u32, u32) {
         (
             (self.crop_origin.0 as i32 - self.buffer as i32),
@@ -162,6 +297,20 @@ impl ClusterBoundingTexture {
         )
     }
 
+    // Size of the buffered texture after downsampling, as seen by the placers
+    pub fn get_scaled_size(&self) -> (u32, u32) {
+        self.get_size_with_factor(self.downsample_factor.value())
+    }
+
+    // Size of the buffered texture if it were downsampled with the given factor
+    pub fn get_size_with_factor(&self, factor: f32) -> (u32, u32) {
+        let (_, _, buffered_width, buffered_height) = self.get_buffered_geometry();
+        (
+            (buffered_width as f32 * factor).max(1.0) as u32,
+            (buffered_height as f32 * factor).max(1.0) as u32,
+        )
+    }
+
     pub fn crop(&self, image: &DynamicImage) -> DynamicImage {
         let (buffered_x, buffered_y, buffered_width, buffered_height) =
             self.get_buffered_geometry();
@@ -241,9 +390,8 @@ impl ClusterBoundingTexture {
             clipped.put_pixel(px, py, *pixel);
         }
 
-        // Downsample
-        let scaled_width = (clipped.width() as f32 * self.downsample_factor.value()) as u32;
-        let scaled_height = (clipped.height() as f32 * self.downsample_factor.value()) as u32;
+        // Downsample to the size the placers reserve, at least 1px
+        let (scaled_width, scaled_height) = self.get_scaled_size();
 
         DynamicImage::ImageRgba8(image::imageops::resize(
             &clipped,
@@ -254,7 +402,8 @@ impl ClusterBoundingTexture {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChildUVPolygon {
     // UV coordinates for the bounding texture (bottom-left origin).
     pub cropped_uv_coords: Vec<(f64, f64)>,
@@ -2,7 +2,6 @@ use std::path::Path;
 
 use image::ImageReader;
 
-#[allow(dead_code)]
 pub fn is_point_inside_polygon(test_point: (f64, f64), polygon: &[(f64, f64)]) -> bool {
     let mut is_inside = false;
     let mut previous_vertex_index = polygon.len() - 1;
@@ -53,3 +52,72 @@ pub fn calc_bbox(pixel_coords: &[(u32, u32)]) -> (u32, u32, u32, u32) {
         },
     )
 }
+
+// Twice the signed area of the triangle (a, b, c)
+fn cross(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+fn point_segment_distance(point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    let (x, y) = (a.0 + t * dx, a.1 + t * dy);
+    ((point.0 - x).powi(2) + (point.1 - y).powi(2)).sqrt()
+}
+
+// Whether a point collinear with the segment lies within its extent
+fn on_segment(point: (f64, f64), segment: ((f64, f64), (f64, f64))) -> bool {
+    let (a, b) = segment;
+    point.0 >= a.0.min(b.0)
+        && point.0 <= a.0.max(b.0)
+        && point.1 >= a.1.min(b.1)
+        && point.1 <= a.1.max(b.1)
+}
+
+fn segments_intersect(a: ((f64, f64), (f64, f64)), b: ((f64, f64), (f64, f64))) -> bool {
+    let (d1, d2) = (cross(b.0, b.1, a.0), cross(b.0, b.1, a.1));
+    let (d3, d4) = (cross(a.0, a.1, b.0), cross(a.0, a.1, b.1));
+    // Proper crossing, or an endpoint lying on the other segment
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+        || d1 == 0.0 && on_segment(a.0, b)
+        || d2 == 0.0 && on_segment(a.1, b)
+        || d3 == 0.0 && on_segment(b.0, a)
+        || d4 == 0.0 && on_segment(b.1, a)
+}
+
+fn edges(ring: &[(f64, f64)]) -> impl Iterator<Item = ((f64, f64), (f64, f64))> + '_ {
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+}
+
+/// Distance between two polygon rings (0 if they intersect or one contains the other)
+pub fn polygon_distance(ring_0: &[(f64, f64)], ring_1: &[(f64, f64)]) -> f64 {
+    if ring_0.is_empty() || ring_1.is_empty() {
+        return f64::INFINITY;
+    }
+    let contains = |ring: &[(f64, f64)], point: (f64, f64)| {
+        ring.len() >= 3 && is_point_inside_polygon(point, ring)
+    };
+    if contains(ring_0, ring_1[0]) || contains(ring_1, ring_0[0]) {
+        return 0.0;
+    }
+
+    let mut distance = f64::INFINITY;
+    for edge_0 in edges(ring_0) {
+        for edge_1 in edges(ring_1) {
+            if segments_intersect(edge_0, edge_1) {
+                return 0.0;
+            }
+            distance = distance
+                .min(point_segment_distance(edge_0.0, edge_1.0, edge_1.1))
+                .min(point_segment_distance(edge_1.0, edge_0.0, edge_0.1));
+        }
+    }
+    distance
+}